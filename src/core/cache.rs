@@ -0,0 +1,93 @@
+// this file contains the caching of parsed records with their resolved regions, so that repeated analyses can skip geocoding
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::core::data::{preload_regions, Record, Region, Source};
+
+/// the version of the cache format, bumped whenever the representation changes
+pub const CACHE_VERSION: u32 = 1;
+
+/// the file name suffix that identifies a cache file when reading input
+pub const CACHE_SUFFIX: &str = ".cache.json";
+
+/// the serialized form of a cache file
+#[derive(Serialize, Deserialize)]
+struct CacheDocument {
+    version: u32,
+    records: Vec<CachedRecord>,
+}
+
+/// the serialized form of a Record, with the codes of the regions it was resolved to
+#[derive(Serialize, Deserialize)]
+struct CachedRecord {
+    latitude: f64,
+    longitude: f64,
+    /// rfc 3339, as in the takeout data
+    timestamp: String,
+    source: Source,
+    accuracy: Option<i64>,
+    regions: Vec<String>,
+}
+
+/// serializes the records and the regions they resolve to as compact JSON
+pub fn records_to_cache(records: &[Record]) -> String {
+    let document = CacheDocument {
+        version: CACHE_VERSION,
+        records: records
+            .iter()
+            .map(|r| CachedRecord {
+                latitude: r.latitude,
+                longitude: r.longitude,
+                timestamp: r.timestamp.to_rfc3339(),
+                source: r.source,
+                accuracy: r.accuracy,
+                regions: r
+                    .regions()
+                    .iter()
+                    .filter_map(|region| region.code().map(|c| c.to_owned()))
+                    .collect(),
+            })
+            .collect(),
+    };
+    serde_json::to_string(&document)
+        .unwrap_or_else(|e| panic!("could not serialize records to cache: {e}"))
+}
+
+/// deserializes the records of a cache file, preloading their regions so they aren't geocoded again
+/// panics if the cache was written by a different version of the format
+pub fn records_from_cache(json_str: &str) -> Vec<Record> {
+    let document: CacheDocument = serde_json::from_str(json_str)
+        .unwrap_or_else(|e| panic!("could not deserialize cache: {e}"));
+    if document.version != CACHE_VERSION {
+        panic!(
+            "could not read cache of version {}, expected version {CACHE_VERSION}; regenerate it from the export",
+            document.version
+        );
+    }
+    document
+        .records
+        .into_iter()
+        .map(|cached| {
+            let record = Record {
+                latitude: cached.latitude,
+                longitude: cached.longitude,
+                timestamp: DateTime::from_str(&cached.timestamp).unwrap_or_else(|e| {
+                    panic!("could not parse cached timestamp {}: {e}", cached.timestamp)
+                }),
+                source: cached.source,
+                accuracy: cached.accuracy,
+            };
+            let regions: BTreeSet<Region> = cached
+                .regions
+                .iter()
+                .map(|c| Region::from_code(c))
+                .collect();
+            preload_regions(&record, regions);
+            record
+        })
+        .collect()
+}
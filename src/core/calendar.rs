@@ -0,0 +1,52 @@
+// this file contains the yearly calendar of the primary country occupied on each day
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Month, NaiveDate};
+use rust_iso3166::CountryCode;
+
+use crate::core::names::localized_country_name;
+
+/// the width of each day's cell in the calendar grid
+const CELL_WIDTH: usize = 3;
+
+/// renders a month-by-month grid for each year, with each day labeled by the alpha-2 code of its primary country
+/// days without an entry, such as days within missing data, are left blank
+/// each year is followed by a legend of the countries that appear in it
+pub fn render_calendar(primary_countries: &BTreeMap<NaiveDate, CountryCode>) -> String {
+    let (Some(first), Some(last)) = (
+        primary_countries.keys().next(),
+        primary_countries.keys().next_back(),
+    ) else {
+        return String::new();
+    };
+
+    let day_header = (1..=31)
+        .map(|d| format!("{d:>CELL_WIDTH$}"))
+        .collect::<String>();
+    let mut years: Vec<String> = vec![];
+    for year in first.year()..=last.year() {
+        let mut lines = vec![format!("{year}"), format!("{:<4}{day_header}", "")];
+        let mut legend: BTreeMap<&str, &str> = BTreeMap::new();
+        for month in 1..=12 {
+            let name = Month::try_from(month as u8).unwrap().name();
+            let cells = (1..=31)
+                .map(|d| {
+                    let label = NaiveDate::from_ymd_opt(year, month, d)
+                        .and_then(|date| primary_countries.get(&date))
+                        .map(|c| {
+                            legend.insert(c.alpha2, localized_country_name(c));
+                            c.alpha2
+                        })
+                        .unwrap_or("");
+                    format!("{label:>CELL_WIDTH$}")
+                })
+                .collect::<String>();
+            lines.push(format!("{:<4}{cells}", &name[..3]).trim_end().to_string());
+        }
+        lines.push(String::new());
+        lines.extend(legend.iter().map(|(code, name)| format!("{code:<4}{name}")));
+        years.push(lines.join("\n"));
+    }
+    years.join("\n\n")
+}
@@ -0,0 +1,116 @@
+// this file contains country-level reference data and queries over visited countries
+
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+
+use clap::ValueEnum;
+use rust_iso3166::CountryCode;
+
+use crate::core::data::{with_parent_countries, Record, Region};
+use crate::core::names::{collation_key, localized_country_name};
+
+/// the continents used to group countries
+#[derive(PartialEq, Eq, Hash, Copy, Clone, ValueEnum, Debug)]
+pub enum Continent {
+    Africa,
+    Antarctica,
+    Asia,
+    Europe,
+    NorthAmerica,
+    Oceania,
+    SouthAmerica,
+}
+
+impl Continent {
+    /// the continent a country belongs to, by ISO 3166-1 alpha-2 code
+    /// transcontinental countries are assigned to the continent they are most commonly grouped with
+    pub fn from_alpha2(alpha2: &str) -> Option<Self> {
+        match alpha2 {
+            "DZ" | "AO" | "BJ" | "BW" | "BF" | "BI" | "CV" | "CM" | "CF" | "TD" | "KM" | "CG"
+            | "CD" | "CI" | "DJ" | "EG" | "GQ" | "ER" | "SZ" | "ET" | "GA" | "GM" | "GH" | "GN"
+            | "GW" | "KE" | "LS" | "LR" | "LY" | "MG" | "MW" | "ML" | "MR" | "MU" | "YT" | "MA"
+            | "MZ" | "NA" | "NE" | "NG" | "RE" | "RW" | "SH" | "ST" | "SN" | "SC" | "SL" | "SO"
+            | "ZA" | "SS" | "SD" | "TZ" | "TG" | "TN" | "UG" | "EH" | "ZM" | "ZW" => {
+                Some(Continent::Africa)
+            }
+            "AQ" | "BV" | "TF" | "HM" | "GS" => Some(Continent::Antarctica),
+            "AF" | "AM" | "AZ" | "BH" | "BD" | "BT" | "IO" | "BN" | "KH" | "CN" | "CX" | "CC"
+            | "GE" | "HK" | "IN" | "ID" | "IR" | "IQ" | "IL" | "JP" | "JO" | "KZ" | "KP" | "KR"
+            | "KW" | "KG" | "LA" | "LB" | "MO" | "MY" | "MV" | "MN" | "MM" | "NP" | "OM" | "PK"
+            | "PS" | "PH" | "QA" | "SA" | "SG" | "LK" | "SY" | "TW" | "TJ" | "TH" | "TL" | "TR"
+            | "TM" | "AE" | "UZ" | "VN" | "YE" => Some(Continent::Asia),
+            "AX" | "AL" | "AD" | "AT" | "BY" | "BE" | "BA" | "BG" | "HR" | "CY" | "CZ" | "DK"
+            | "EE" | "FO" | "FI" | "FR" | "DE" | "GI" | "GR" | "GG" | "HU" | "IS" | "IE" | "IM"
+            | "IT" | "JE" | "XK" | "LV" | "LI" | "LT" | "LU" | "MT" | "MD" | "MC" | "ME" | "NL"
+            | "MK" | "NO" | "PL" | "PT" | "RO" | "RU" | "SM" | "RS" | "SK" | "SI" | "ES" | "SJ"
+            | "SE" | "CH" | "UA" | "GB" | "VA" => Some(Continent::Europe),
+            "AI" | "AG" | "AW" | "BS" | "BB" | "BZ" | "BM" | "BQ" | "VG" | "CA" | "KY" | "CR"
+            | "CU" | "CW" | "DM" | "DO" | "SV" | "GL" | "GD" | "GP" | "GT" | "HT" | "HN" | "JM"
+            | "MQ" | "MX" | "MS" | "NI" | "PA" | "PR" | "BL" | "KN" | "LC" | "MF" | "PM" | "VC"
+            | "SX" | "TT" | "TC" | "US" | "VI" | "UM" => Some(Continent::NorthAmerica),
+            "AS" | "AU" | "CK" | "FJ" | "PF" | "GU" | "KI" | "MH" | "FM" | "NR" | "NC" | "NZ"
+            | "NU" | "NF" | "MP" | "PW" | "PG" | "PN" | "WS" | "SB" | "TK" | "TO" | "TV" | "VU"
+            | "WF" => Some(Continent::Oceania),
+            "AR" | "BO" | "BR" | "CL" | "CO" | "EC" | "FK" | "GF" | "GY" | "PY" | "PE" | "SR"
+            | "UY" | "VE" => Some(Continent::SouthAmerica),
+            _ => None,
+        }
+    }
+}
+
+/// the country a dependent territory belongs to, by ISO 3166-1 alpha-2 code, or the code itself for a country that is not a dependency
+/// the boundary data puts a point in a territory in both the territory and its country, such as Puerto Rico and the United States, which is not an ambiguity between countries
+pub fn sovereign_of(alpha2: &str) -> &str {
+    match alpha2 {
+        "AS" | "GU" | "MP" | "PR" | "UM" | "VI" => "US",
+        "AI" | "BM" | "FK" | "GG" | "GI" | "GS" | "IM" | "IO" | "JE" | "KY" | "MS" | "PN"
+        | "SH" | "TC" | "VG" => "GB",
+        "BL" | "GF" | "GP" | "MF" | "MQ" | "NC" | "PF" | "PM" | "RE" | "TF" | "WF" | "YT" => "FR",
+        "AW" | "BQ" | "CW" | "SX" => "NL",
+        "FO" | "GL" => "DK",
+        "BV" | "SJ" => "NO",
+        "CC" | "CX" | "HM" | "NF" => "AU",
+        "CK" | "NU" | "TK" => "NZ",
+        "HK" | "MO" => "CN",
+        "AX" => "FI",
+        _ => alpha2,
+    }
+}
+
+impl Display for Continent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Continent::Africa => "Africa",
+            Continent::Antarctica => "Antarctica",
+            Continent::Asia => "Asia",
+            Continent::Europe => "Europe",
+            Continent::NorthAmerica => "North America",
+            Continent::Oceania => "Oceania",
+            Continent::SouthAmerica => "South America",
+        };
+        write!(f, "{str}")
+    }
+}
+
+/// lists the countries that none of the records fall within, sorted by their name in the language chosen for the run
+/// a record in a subdivision is within its parent country, even where the boundary data has the subdivision but not the country
+/// if a continent is given, only countries on that continent are listed
+pub fn countries_not_visited(records: &[Record], continent: Option<Continent>) -> Vec<CountryCode> {
+    let visited: HashSet<CountryCode> = records
+        .iter()
+        .flat_map(|r| with_parent_countries(&r.regions()))
+        .filter_map(|r| match r {
+            Region::CountryCode(c) => Some(c),
+            _ => None,
+        })
+        .collect();
+
+    let mut not_visited: Vec<CountryCode> = rust_iso3166::ALL
+        .iter()
+        .filter(|c| !visited.contains(c))
+        .filter(|c| continent.is_none() || Continent::from_alpha2(c.alpha2) == continent)
+        .copied()
+        .collect();
+    not_visited.sort_by_cached_key(|c| collation_key(localized_country_name(c)));
+    not_visited
+}
@@ -0,0 +1,475 @@
+// this file contains the detection of border crossings from a sequence of records, and the passes that clean them up
+
+use std::collections::{BTreeSet, HashSet};
+
+use chrono::TimeDelta;
+use clap::ValueEnum;
+use log::{debug, warn};
+
+use crate::core::countries::sovereign_of;
+use crate::core::data::{
+    with_parent_countries, BorderCrossing, CrossingReason, Record, Region, Source,
+    MISSING_DATA_THRESHOLD,
+};
+
+/// strips crossings that enter no regions beyond those of the crossing before them
+/// after stripping out certain types of border crossings, we may have crossings next to each other that no longer differ
+/// consider the original data [Muffintown, Missing Data, Muffintown]; if we strip Missing Data, we're now left with [Muffintown, Muffintown] as two separate, consecutive border crossings
+pub fn collapse_consecutive_duplicates(crossings: &[BorderCrossing]) -> Vec<BorderCrossing> {
+    compare_and_retain(crossings, |c, p| {
+        !(&c.new_regions - &p.new_regions).is_empty() // if the crossing entries' regions differ by at least one, the crossing can be retained
+    })
+}
+
+/// strips crossings that enter no new countries, only new subdivisions
+/// regions with subdivisions but no country are compared by the parent countries of their subdivisions, so that where the boundary data omits the country a move between countries is still kept
+pub fn drop_subregion_crossings(crossings: &[BorderCrossing]) -> Vec<BorderCrossing> {
+    compare_and_retain(crossings, |c, p| {
+        (&with_parent_countries(&c.new_regions) - &with_parent_countries(&p.new_regions))
+            .iter()
+            .any(|r| !r.is_subregion())
+    })
+}
+
+/// replaces each obsolete region in the crossings with its present-day successor, where the mapping is unique
+/// obsolete regions that were split into several countries are left as-is, and noted once each
+pub fn remap_obsolete_regions(crossings: &mut [BorderCrossing]) {
+    let mut noted: HashSet<Region> = HashSet::new();
+    for crossing in crossings.iter_mut() {
+        crossing.new_regions = crossing
+            .new_regions
+            .iter()
+            .map(|r| match (r, r.successor()) {
+                (_, Some(successor)) => Region::CountryCode(successor),
+                (Region::Obsolete(o), None) => {
+                    if noted.insert(r.clone()) {
+                        warn!(
+                            "could not remap {} ({}) to a single successor: {}",
+                            o.code,
+                            o.name,
+                            o.desc.trim()
+                        );
+                    }
+                    r.clone()
+                }
+                _ => r.clone(),
+            })
+            .collect();
+    }
+}
+
+/// drops the region changes that don't reach at least buffer_km into the regions entered, such as when a track grazes a border
+/// the boundary data doesn't give the distance to a border, so this is approximated by the distance traveled while in the new regions:
+/// the length of the path through the records from the crossing up to the next crossing must be at least buffer_km
+/// the path length also counts any back-and-forth jitter, so a long stay just across a border can still be confirmed
+/// a region change with a single record before the next crossing has no path, so it is always dropped
+/// first records and the crossings around missing data are always kept, since they don't follow a move from a known region
+/// so are crossings corroborated by a flight, which are real moves however little of the new regions the records cover
+/// the crossing back out of a dropped region change is left for collapse_consecutive_duplicates to remove
+/// requires records and crossings to be sorted by timestamp, with the crossings detected from the records
+pub fn drop_shallow_crossings(
+    crossings: &[BorderCrossing],
+    records: &[Record],
+    buffer_km: f64,
+) -> Vec<BorderCrossing> {
+    crossings
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| {
+            if c.reason != CrossingReason::RegionChange || c.flight.is_some() {
+                return true;
+            }
+            let start = records.partition_point(|r| r.timestamp < c.timestamp);
+            let end = match crossings.get(i + 1) {
+                Some(next) => records.partition_point(|r| r.timestamp < next.timestamp),
+                None => records.len(),
+            };
+            let traveled_km: f64 = records[start..end.max(start)]
+                .windows(2)
+                .map(|pair| pair[0].distance_km(&pair[1]))
+                .sum();
+            if traveled_km < buffer_km {
+                debug!(
+                    "dropped crossing at {} after traveling {traveled_km:.2} km into [{}]",
+                    c.timestamp.to_rfc3339(),
+                    region_codes(&c.new_regions)
+                );
+            }
+            traveled_km >= buffer_km
+        })
+        .map(|(_, c)| c.clone())
+        .collect()
+}
+
+/// the crossings in new that come after the last crossing in old, for appending a newer export's crossings to an older one's
+/// if old has no crossings, every crossing in new is returned
+pub fn crossings_since(old: &[BorderCrossing], new: &[BorderCrossing]) -> Vec<BorderCrossing> {
+    let Some(last) = old.last() else {
+        return new.to_vec();
+    };
+    new.iter()
+        .filter(|c| c.timestamp > last.timestamp)
+        .cloned()
+        .collect()
+}
+
+/// an attribute of crossings that they can be counted by, instead of being listed
+#[derive(PartialEq, Eq, Copy, Clone, ValueEnum, Debug)]
+pub enum GroupBy {
+    /// the source of the record that triggered each crossing
+    Source,
+}
+
+/// the number of crossings triggered by records of each source, in the order the sources are declared
+/// missing data crossings are not triggered by a record, so they aren't counted
+pub fn crossings_by_source(crossings: &[BorderCrossing]) -> Vec<(Source, usize)> {
+    Source::value_variants()
+        .iter()
+        .map(|source| {
+            let count = crossings
+                .iter()
+                .filter(|c| c.source == *source && !c.new_regions.contains(&Region::MissingData))
+                .count();
+            (*source, count)
+        })
+        .collect()
+}
+
+/// compares each element in v to its predecessor using the given predicate
+/// predicate is (current, previous) -> bool
+/// if the predicate returns true, the element is placed in the returned Vec
+/// if the predicate returns false, the element will not be contained in the returned Vec
+/// the first element in v is always included, because there is no previous element to compare to
+pub fn compare_and_retain<T: Clone>(v: &[T], predicate: impl Fn(&T, &T) -> bool) -> Vec<T> {
+    let mut new_vec: Vec<&T> = vec![];
+    for i in 0..v.len() {
+        let element = v.get(i).unwrap();
+        let previous_element = i.checked_sub(1).and_then(|j| v.get(j));
+        match previous_element {
+            Some(prev) => {
+                if predicate(element, prev) {
+                    new_vec.push(element)
+                }
+            }
+            // if there is no previous element, it is by default included
+            None => new_vec.push(element),
+        }
+    }
+
+    new_vec.iter().map(|&item| item.clone()).collect()
+}
+
+/// options controlling how border crossings are detected from records
+#[derive(Default)]
+pub struct DetectionOptions {
+    /// assume the region stays the same across a data gap when the records on either side of it are in the same regions, rather than inserting missing data
+    pub interpolate_missing: bool,
+    /// when interpolating, only gaps shorter than this are interpolated; longer gaps are still missing data
+    pub max_gap_fill: Option<TimeDelta>,
+    /// reduce each record's regions to countries before detection, so that moving between subdivisions is never a crossing
+    pub countries_only: bool,
+    /// replace each subdivision in a record's regions with its parent country before detection, even when the boundary data also has a different country
+    /// unlike countries_only, a subdivision always contributes its country rather than only when no country is present
+    pub collapse_subdivisions: bool,
+    /// reduce each record's regions to the single region chosen by primary_region, so that a point resolving to several countries near a border is in only one
+    pub primary_only: bool,
+    /// treat valid coordinates outside of every region as being in international waters, rather than in no region
+    pub track_ocean: bool,
+    /// replace the regions of a record that is in several countries at once with a single border zone between them
+    /// entering a border zone that includes a country the previous record was in is not a crossing, since the zone doesn't resolve where the record was
+    pub border_zones: bool,
+}
+
+impl DetectionOptions {
+    /// the regions a record is considered to be in for the purpose of detection
+    /// when reducing to countries, subdivisions are replaced by their parent country if the boundary data has none
+    /// when collapsing subdivisions, every subdivision is replaced by its parent country
+    /// when keeping only the primary region, the regions are then reduced to the one chosen by primary_region
+    /// when tracking the ocean, a record outside of every region is in international waters
+    /// when using border zones, a record in several countries is in the border zone between them, after all of the above
+    pub fn regions(&self, record: &Record) -> BTreeSet<Region> {
+        let regions = record.regions();
+        if self.track_ocean && regions.is_empty() && record.has_valid_coordinates() {
+            return BTreeSet::from([Region::InternationalWaters]);
+        }
+        let regions = if self.collapse_subdivisions {
+            regions
+                .iter()
+                .map(|r| match r.parent_country() {
+                    Some(parent) => Region::CountryCode(parent),
+                    None => r.clone(),
+                })
+                .collect()
+        } else if self.countries_only {
+            with_parent_countries(&regions)
+                .into_iter()
+                .filter(|r| !r.is_subregion())
+                .collect()
+        } else {
+            regions
+        };
+        let regions = if self.primary_only {
+            primary_region(&regions).into_iter().collect()
+        } else {
+            regions
+        };
+        if self.border_zones {
+            border_zone(&regions)
+                .map(|zone| BTreeSet::from([zone]))
+                .unwrap_or(regions)
+        } else {
+            regions
+        }
+    }
+}
+
+/// the single region a set of regions is reduced to when keeping only the primary region
+/// this is a country wherever there is one: the country of the lowest alpha-2 code, or if there are only subdivisions, the parent country of the subdivision of the lowest code
+/// only if there is no country at all, such as for an obsolete or unknown code, is it the region of the lowest code
+/// the boundary data doesn't say how far inside each region a point is, so ties between countries at a border are broken by code alone
+pub fn primary_region(regions: &BTreeSet<Region>) -> Option<Region> {
+    let with_parents = with_parent_countries(regions);
+    with_parents
+        .iter()
+        .find(|r| matches!(r, Region::CountryCode(_)))
+        .or_else(|| with_parents.first())
+        .cloned()
+}
+
+/// the border zone between the countries of the regions, if they belong to at least two different countries
+/// subdivisions count towards the country they belong to, so a point in a country and a subdivision of another is in a border zone
+/// dependent territories count towards their country, so a point in both a territory and its country is not in a border zone
+pub fn border_zone(regions: &BTreeSet<Region>) -> Option<Region> {
+    let mut countries: Vec<rust_iso3166::CountryCode> = regions
+        .iter()
+        .filter_map(|r| match r {
+            Region::CountryCode(c) => Some(*c),
+            _ => r.parent_country(),
+        })
+        .collect();
+    countries.sort_by_key(|c| c.alpha2);
+    countries.dedup_by_key(|c| c.alpha2);
+    let sovereigns: BTreeSet<&str> = countries.iter().map(|c| sovereign_of(c.alpha2)).collect();
+    (sovereigns.len() > 1).then_some(Region::BorderZone(countries))
+}
+
+/// the alpha-2 codes of the countries the regions place a record in, counting each country of a border zone
+fn countries_of(regions: &BTreeSet<Region>) -> BTreeSet<&'static str> {
+    regions
+        .iter()
+        .flat_map(|r| match r {
+            Region::CountryCode(c) => vec![c.alpha2],
+            Region::BorderZone(countries) => countries.iter().map(|c| c.alpha2).collect(),
+            _ => r.parent_country().map(|c| c.alpha2).into_iter().collect(),
+        })
+        .collect()
+}
+
+/// whether entering cur from prev leaves the location unresolved: cur is a border zone that includes a country prev was in
+fn is_unresolved(prev: &BTreeSet<Region>, cur: &BTreeSet<Region>) -> bool {
+    matches!(cur.first(), Some(Region::BorderZone(_)))
+        && !countries_of(prev).is_disjoint(&countries_of(cur))
+}
+
+/// requires records to be sorted by timestamp
+pub fn records_to_border_crossings(records: &[Record]) -> Vec<BorderCrossing> {
+    detect_border_crossings(records, &DetectionOptions::default(), None)
+}
+
+/// requires records to be sorted by timestamp
+/// if trace is given, a line describing the decision made for each record is appended to it
+pub fn detect_border_crossings(
+    records: &[Record],
+    options: &DetectionOptions,
+    mut trace: Option<&mut Vec<String>>,
+) -> Vec<BorderCrossing> {
+    let mut crossings: Vec<BorderCrossing> = vec![];
+    // each record's regions are resolved once, and carried over to be compared against by the next record
+    let mut prev: Option<(&Record, BTreeSet<Region>)> = None;
+    for record in records {
+        let regions = options.regions(record);
+        let prev_located = prev.as_ref().map(|(r, regions)| (*r, regions));
+        let step_crossings = step_between(
+            prev_located,
+            (record, &regions),
+            MISSING_DATA_THRESHOLD,
+            options,
+        );
+        trace_step(
+            &mut trace,
+            prev_located,
+            (record, &regions),
+            &step_crossings,
+        );
+        crossings.extend(step_crossings);
+        prev = Some((record, regions));
+    }
+    crossings
+}
+
+/// decides which crossings a record produces, given the record before it
+/// returns no crossings if the regions are unchanged, one if the record enters new regions (or is the first record),
+/// or two if the records are at least gap apart: a missing data crossing followed by the record's own crossing
+/// the missing data crossing is stamped one gap after the previous record, the point at which the data is first considered missing
+/// the record after missing data is always a crossing, even if its regions are unchanged, since the missing data has to end somewhere
+/// so consecutive gaps never produce back-to-back missing data crossings: each is followed by the crossing of the record that ends it
+pub fn step(
+    prev: Option<&Record>,
+    cur: &Record,
+    gap: TimeDelta,
+    options: &DetectionOptions,
+) -> Vec<BorderCrossing> {
+    let prev_regions = prev.map(|p| options.regions(p));
+    step_between(
+        prev.zip(prev_regions.as_ref()),
+        (cur, &options.regions(cur)),
+        gap,
+        options,
+    )
+}
+
+/// the same as step, for records whose regions under the options were already resolved
+fn step_between(
+    prev: Option<(&Record, &BTreeSet<Region>)>,
+    (cur, cur_regions): (&Record, &BTreeSet<Region>),
+    gap: TimeDelta,
+    options: &DetectionOptions,
+) -> Vec<BorderCrossing> {
+    let crossing = |reason: CrossingReason| BorderCrossing {
+        new_regions: cur_regions.clone(),
+        reason,
+        ..BorderCrossing::from(cur)
+    };
+    // if there is no previous record, we unconditionally make a border crossing
+    let Some((prev, prev_regions)) = prev else {
+        return vec![crossing(CrossingReason::FirstRecord)];
+    };
+    let interval = cur.timestamp - prev.timestamp;
+    if interval >= gap {
+        let fillable = options.max_gap_fill.is_none_or(|max| interval < max);
+        if options.interpolate_missing && fillable && cur_regions == prev_regions {
+            // the gap is bracketed by the same regions, so assume we never left
+            return vec![];
+        }
+        debug!(
+            "detected gap of {} hours at {}",
+            interval.num_hours(),
+            prev.timestamp.to_rfc3339()
+        );
+        let missing_data = BorderCrossing {
+            timestamp: prev.timestamp + gap,
+            new_regions: vec![Region::MissingData].into_iter().collect(),
+            coordinates: None,
+            reason: CrossingReason::Gap,
+            source: Source::None,
+            flight: None,
+        };
+        vec![missing_data, crossing(CrossingReason::PostGap)]
+    } else if (cur_regions - prev_regions).is_empty()
+        || (options.border_zones && is_unresolved(prev_regions, cur_regions))
+    {
+        vec![]
+    } else {
+        vec![crossing(CrossingReason::RegionChange)]
+    }
+}
+
+/// appends the lines describing a record and explaining the crossings its step produced to the trace, if tracing is enabled
+fn trace_step(
+    trace: &mut Option<&mut Vec<String>>,
+    prev: Option<(&Record, &BTreeSet<Region>)>,
+    (cur, cur_regions): (&Record, &BTreeSet<Region>),
+    crossings: &[BorderCrossing],
+) {
+    if trace.is_none() {
+        return;
+    }
+    push_trace(trace, || {
+        format!(
+            "{} ({}, {}) {:?} regions: [{}]",
+            cur.timestamp.to_rfc3339(),
+            cur.latitude,
+            cur.longitude,
+            cur.source,
+            region_codes(cur_regions)
+        )
+    });
+    let Some((prev, prev_regions)) = prev else {
+        push_trace(trace, || "    crossing: first record".to_string());
+        return;
+    };
+    let interval = cur.timestamp - prev.timestamp;
+    let location_diff = cur_regions - prev_regions;
+    match crossings {
+        [missing_data, _] => {
+            push_trace(trace, || {
+                format!(
+                    "    gap of {} hours since previous record, inserted missing data at {}",
+                    interval.num_hours(),
+                    missing_data.timestamp.to_rfc3339()
+                )
+            });
+            if location_diff.is_empty() {
+                push_trace(trace, || {
+                    "    crossing: resumed after missing data".to_string()
+                });
+            } else {
+                push_trace(trace, || {
+                    format!(
+                        "    crossing: entered new regions [{}]",
+                        region_codes(&location_diff)
+                    )
+                });
+            }
+        }
+        [_] => push_trace(trace, || {
+            format!(
+                "    crossing: entered new regions [{}]",
+                region_codes(&location_diff)
+            )
+        }),
+        _ => {
+            if interval >= MISSING_DATA_THRESHOLD {
+                push_trace(trace, || {
+                    format!(
+                        "    gap of {} hours since previous record, interpolated across the same regions",
+                        interval.num_hours()
+                    )
+                });
+            }
+            if location_diff.is_empty() {
+                push_trace(trace, || "    no crossing: regions unchanged".to_string());
+            } else {
+                // step only suppresses a change of regions when it enters an unresolved border zone
+                push_trace(trace, || {
+                    let countries = countries_of(cur_regions);
+                    format!(
+                        "    no crossing: within border zone [{}]",
+                        countries.into_iter().collect::<Vec<&str>>().join("/")
+                    )
+                });
+            }
+        }
+    }
+}
+
+/// appends a line to the trace, if tracing is enabled
+/// the line is built lazily so that tracing costs nothing when disabled
+pub fn push_trace(trace: &mut Option<&mut Vec<String>>, line: impl FnOnce() -> String) {
+    if let Some(t) = trace.as_deref_mut() {
+        t.push(line());
+    }
+}
+
+/// lists the codes of the given regions, for diagnostic output
+pub fn region_codes(regions: &BTreeSet<Region>) -> String {
+    regions
+        .iter()
+        .map(|r| {
+            r.code()
+                .map(|c| c.to_owned())
+                .unwrap_or_else(|| r.to_string())
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
@@ -1,24 +1,133 @@
 // this file contains type/data definitions for internal use
 
-use std::collections::HashSet;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::Display;
-use std::ops::Deref;
+use std::sync::{OnceLock, RwLock};
 use std::{fmt, str::FromStr};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Offset, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
 use clap::ValueEnum;
-use country_boundaries::{CountryBoundaries, LatLon, BOUNDARIES_ODBL_360X180};
+use country_boundaries::{
+    CountryBoundaries, LatLon, BOUNDARIES_ODBL_180X90, BOUNDARIES_ODBL_360X180,
+    BOUNDARIES_ODBL_60X30,
+};
 use lazy_static::lazy_static;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use tzf_rs::DefaultFinder;
 
-use crate::{JsonRecord, JsonSource};
+use crate::core::json::{JsonRecord, JsonSource};
+use crate::core::names::{
+    border_zone_name, country_name, international_waters_name, language, localized_country_name,
+    missing_data_name, Language,
+};
+use crate::core::semantic::Flight;
+
+// keeps country boundaries data in memory, loaded at the chosen resolution on first use
+static BOUNDARIES: OnceLock<CountryBoundaries> = OnceLock::new();
 
 lazy_static! {
-    // keeps country boundaries data in memory
-    static ref BOUNDARIES: CountryBoundaries = CountryBoundaries::from_reader(BOUNDARIES_ODBL_360X180)
-        .unwrap_or_else(|e| panic!("could not read boundaries: {e}"));
+    // keeps timezone boundaries data in memory
+    static ref TIMEZONES: DefaultFinder = DefaultFinder::new();
+    // the regions of records read from a cache, keyed by the bits of their coordinates, so they aren't geocoded again
+    static ref PRELOADED_REGIONS: RwLock<HashMap<(u64, u64), BTreeSet<Region>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// the grid resolution of the country boundaries dataset
+/// finer grids classify coordinates near borders and coastlines more accurately, at the cost of memory and load time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum BoundaryResolution {
+    /// a 360x180 grid, the finest available
+    #[default]
+    High,
+    /// a 180x90 grid
+    Medium,
+    /// a 60x30 grid
+    Low,
+}
+
+impl BoundaryResolution {
+    fn data(&self) -> &'static [u8] {
+        match self {
+            BoundaryResolution::High => BOUNDARIES_ODBL_360X180,
+            BoundaryResolution::Medium => BOUNDARIES_ODBL_180X90,
+            BoundaryResolution::Low => BOUNDARIES_ODBL_60X30,
+        }
+    }
+}
+
+fn read_boundaries(resolution: BoundaryResolution) -> CountryBoundaries {
+    CountryBoundaries::from_reader(resolution.data())
+        .unwrap_or_else(|e| panic!("could not read boundaries: {e}"))
 }
 
+/// loads the country boundaries dataset at the given resolution
+/// must be called before any coordinates are looked up, otherwise the default resolution has already been loaded
+pub fn load_boundaries(resolution: BoundaryResolution) {
+    if BOUNDARIES.set(read_boundaries(resolution)).is_err() {
+        panic!("could not load boundaries at {resolution:?} resolution: boundaries were already loaded");
+    }
+}
+
+fn boundaries() -> &'static CountryBoundaries {
+    BOUNDARIES.get_or_init(|| read_boundaries(BoundaryResolution::default()))
+}
+
+// the timezone assumed for timestamps without an offset, chosen once per run
+static NAIVE_TIMESTAMP_ZONE: OnceLock<NaiveTimestampZone> = OnceLock::new();
+
+/// the timezone assumed for timestamps recorded without an offset, which some exports contain
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NaiveTimestampZone {
+    /// the timestamp is in UTC
+    #[default]
+    Utc,
+    /// the timestamp is in the local time of the record's coordinates, falling back to UTC where the timezone isn't recognized
+    Local,
+}
+
+impl NaiveTimestampZone {
+    /// attaches the assumed timezone to a timestamp without an offset
+    /// a local time skipped by a daylight saving transition is taken at the offset in effect just before it
+    fn resolve(&self, naive: NaiveDateTime, latitude: f64, longitude: f64) -> DateTime<Utc> {
+        let tz = match self {
+            NaiveTimestampZone::Utc => None,
+            NaiveTimestampZone::Local => timezone_at(latitude, longitude),
+        };
+        match tz {
+            Some(tz) => tz
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|| {
+                    let offset = tz.offset_from_utc_datetime(&naive).fix();
+                    Utc.from_utc_datetime(&(naive - offset))
+                }),
+            None => Utc.from_utc_datetime(&naive),
+        }
+    }
+}
+
+/// sets the timezone assumed for timestamps without an offset
+/// must be called before any records are parsed, otherwise UTC has already been assumed
+pub fn assume_naive_timestamp_zone(zone: NaiveTimestampZone) {
+    if NAIVE_TIMESTAMP_ZONE.set(zone).is_err() {
+        panic!("could not assume {zone:?} for timestamps without an offset: records were already parsed");
+    }
+}
+
+/// the distance in degrees from (0, 0) within which a record is considered a null island artifact
+pub const NULL_ISLAND_EPSILON: f64 = 1e-5;
+
+/// a gap between consecutive records of at least this long is treated as missing data
+/// the resulting missing data crossing is stamped this long after the last record before the gap
+pub const MISSING_DATA_THRESHOLD: TimeDelta = TimeDelta::days(1);
+
+/// mean radius of the earth, used for great-circle distances
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
 /// this is a cleaner, more usable version of the raw JSON JsonRecord type from Google Takeout (in json.rs)
 #[derive(Clone, Copy, Debug)]
 pub struct Record {
@@ -26,40 +135,271 @@ pub struct Record {
     pub longitude: f64,
     pub timestamp: DateTime<Utc>,
     pub source: Source,
+    /// the estimated accuracy radius in meters, if recorded
+    pub accuracy: Option<i64>,
 }
 
 impl Record {
-    pub fn from_json(json: &JsonRecord) -> Option<Self> {
-        if let (Some(latitude), Some(longitude), timestamp) =
-            (json.latitude, json.longitude, &json.timestamp)
-        {
-            Some(Record {
-                latitude: latitude as f64 / 1E7,
-                longitude: longitude as f64 / 1E7,
-                timestamp: DateTime::from_str(&timestamp).unwrap(),
-                source: Source::from_json_source(&json.source),
+    /// converts a raw JSON record, rejecting records without coordinates, with coordinates beyond the valid range, or with an unparseable timestamp
+    /// a timestamp without an offset is taken to be in the assumed timezone, UTC unless set otherwise
+    pub fn from_json(json: &JsonRecord) -> Result<Self, RecordError> {
+        let (Some(latitude), Some(longitude)) = (json.latitude, json.longitude) else {
+            return Err(RecordError::MissingCoordinates);
+        };
+        let (latitude, longitude) = (latitude as f64 / 1E7, longitude as f64 / 1E7);
+        let timestamp = match DateTime::from_str(&json.timestamp) {
+            Ok(timestamp) => timestamp,
+            Err(_) => {
+                let naive = NaiveDateTime::from_str(&json.timestamp)
+                    .map_err(|_| RecordError::InvalidTimestamp)?;
+                NAIVE_TIMESTAMP_ZONE
+                    .get_or_init(NaiveTimestampZone::default)
+                    .resolve(naive, latitude, longitude)
+            }
+        };
+        let record = Record {
+            latitude,
+            longitude,
+            timestamp,
+            source: Source::from_json_source(&json.source),
+            accuracy: json.accuracy,
+        };
+        // checked here so that a corrupt value is a counted skip rather than a panic once the boundaries are queried
+        if !record.has_valid_coordinates() {
+            return Err(RecordError::OutOfRangeCoordinates);
+        }
+        Ok(record)
+    }
+
+    /// the great-circle distance in kilometers between this record and another, using the haversine formula
+    pub fn distance_km(&self, other: &Record) -> f64 {
+        let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.longitude - self.longitude).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+    }
+
+    /// the points along the great-circle path from this record to another, spaced at most step_km apart, including both ends
+    /// nearly antipodal records have no unique great circle between them, so only their ends are returned
+    pub fn great_circle_path(&self, other: &Record, step_km: f64) -> Vec<(f64, f64)> {
+        let to_vector = |r: &Record| {
+            let (lat, lon) = (r.latitude.to_radians(), r.longitude.to_radians());
+            [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+        };
+        let (a, b) = (to_vector(self), to_vector(other));
+        let angle = self.distance_km(other) / EARTH_RADIUS_KM;
+        let ends = vec![
+            (self.latitude, self.longitude),
+            (other.latitude, other.longitude),
+        ];
+        if angle.sin().abs() < f64::EPSILON {
+            return ends;
+        }
+        let steps = (self.distance_km(other) / step_km).ceil().max(1.0) as usize;
+        (0..=steps)
+            .map(|i| {
+                // spherical linear interpolation between the two points as unit vectors
+                let fraction = i as f64 / steps as f64;
+                let weight_a = ((1.0 - fraction) * angle).sin() / angle.sin();
+                let weight_b = (fraction * angle).sin() / angle.sin();
+                let [x, y, z] = [0, 1, 2].map(|k| weight_a * a[k] + weight_b * b[k]);
+                (z.atan2(x.hypot(y)).to_degrees(), y.atan2(x).to_degrees())
             })
+            .collect()
+    }
+
+    /// how confidently the record is in its regions, given its accuracy, from 0 to 1
+    /// this is the share of the points on its accuracy circle, at each of the eight compass directions, that are in the same regions as its center
+    /// a record whose accuracy circle straddles a boundary has a lower confidence; records without an accuracy are fully confident
+    pub fn confidence(&self) -> f64 {
+        let Some(accuracy) = self.accuracy.filter(|a| *a > 0) else {
+            return 1.0;
+        };
+        let regions = self.regions();
+        let radius_deg = (accuracy as f64 / 1000.0 / EARTH_RADIUS_KM).to_degrees();
+        let lon_scale = self.latitude.to_radians().cos().max(f64::EPSILON);
+        let (agreeing, total) = (0..8)
+            .map(|i| (i as f64 * std::f64::consts::FRAC_PI_4).sin_cos())
+            .filter_map(|(sin, cos)| {
+                // points beyond the poles or the antimeridian are skipped rather than wrapped
+                regions_at(
+                    self.latitude + radius_deg * cos,
+                    self.longitude + radius_deg * sin / lon_scale,
+                )
+                .ok()
+            })
+            .fold((0, 0), |(agreeing, total), r| {
+                (agreeing + usize::from(r == regions), total + 1)
+            });
+        if total == 0 {
+            1.0
         } else {
-            None
+            agreeing as f64 / total as f64
         }
     }
 
-    pub fn regions(&self) -> HashSet<Region> {
-        BOUNDARIES
-            .deref()
-            .ids(
-                LatLon::new(self.latitude, self.longitude).unwrap_or_else(|e| {
-                    panic!("could not find region code for record {self:?}: {e}")
-                }),
-            )
-            .iter()
-            .map(|code| Region::from_code(code))
-            .collect()
+    /// orders records by timestamp, breaking ties by source priority and then by coordinate
+    /// this makes the order of records at the same instant reproducible
+    pub fn cmp_chronological(&self, other: &Record) -> Ordering {
+        self.timestamp
+            .cmp(&other.timestamp)
+            .then(self.source.priority().cmp(&other.source.priority()))
+            .then(self.latitude.total_cmp(&other.latitude))
+            .then(self.longitude.total_cmp(&other.longitude))
+    }
+
+    /// whether the coordinates are within the valid range of latitudes and longitudes
+    /// the ranges are checked explicitly, since the boundary data tolerates longitudes slightly beyond them
+    pub fn has_valid_coordinates(&self) -> bool {
+        (-90.0..=90.0).contains(&self.latitude)
+            && (-180.0..=180.0).contains(&self.longitude)
+            && LatLon::new(self.latitude, self.longitude).is_ok()
+    }
+
+    /// whether the coordinates are at (0, 0), where corrupt exports place records that are missing a real fix
+    pub fn is_null_island(&self) -> bool {
+        self.latitude.abs() < NULL_ISLAND_EPSILON && self.longitude.abs() < NULL_ISLAND_EPSILON
+    }
+
+    /// the regions containing the record, as preloaded from a cache or else looked up in the boundary data; none for invalid coordinates
+    pub fn regions(&self) -> BTreeSet<Region> {
+        let preloaded = PRELOADED_REGIONS
+            .read()
+            .unwrap_or_else(|e| panic!("could not read preloaded regions: {e}"))
+            .get(&self.coordinate_bits())
+            .cloned();
+        preloaded.unwrap_or_else(|| regions_at(self.latitude, self.longitude).unwrap_or_default())
+    }
+
+    /// the exact bits of the coordinates, for use as a key
+    fn coordinate_bits(&self) -> (u64, u64) {
+        (self.latitude.to_bits(), self.longitude.to_bits())
+    }
+}
+
+/// a box of coordinates used to filter records to a region of interest
+#[derive(Debug, Clone, Copy)]
+pub struct BoundingBox {
+    pub min_latitude: f64,
+    pub min_longitude: f64,
+    pub max_latitude: f64,
+    pub max_longitude: f64,
+}
+
+impl BoundingBox {
+    /// whether the record lies within the box, inclusive of its edges
+    /// if min_longitude is greater than max_longitude, the box wraps across the antimeridian
+    pub fn contains(&self, record: &Record) -> bool {
+        let within_latitude =
+            self.min_latitude <= record.latitude && record.latitude <= self.max_latitude;
+        let within_longitude = if self.min_longitude <= self.max_longitude {
+            self.min_longitude <= record.longitude && record.longitude <= self.max_longitude
+        } else {
+            self.min_longitude <= record.longitude || record.longitude <= self.max_longitude
+        };
+        within_latitude && within_longitude
+    }
+}
+
+/// the reason a raw JSON record could not be converted to a Record
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RecordError {
+    MissingCoordinates,
+    OutOfRangeCoordinates,
+    InvalidTimestamp,
+}
+
+/// counts of the raw JSON records that were kept or dropped while parsing a document
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParseStats {
+    pub total: usize,
+    pub kept: usize,
+    pub dropped_no_coords: usize,
+    pub dropped_out_of_range: usize,
+    pub dropped_bad_timestamp: usize,
+    /// whether the document was cut off, so that only the records before the truncation were read
+    pub truncated: bool,
+    /// whether there was no data at all, such as a file that was created but never written to
+    pub empty: bool,
+}
+
+impl ParseStats {
+    pub fn dropped(&self) -> usize {
+        self.dropped_no_coords + self.dropped_out_of_range + self.dropped_bad_timestamp
     }
 }
 
+impl Display for ParseStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.empty {
+            return write!(f, "the file is empty, so there are no records to read");
+        }
+        write!(
+            f,
+            "read {} records: kept {}, dropped {} without coordinates, dropped {} with out-of-range coordinates, dropped {} with invalid timestamps",
+            self.total,
+            self.kept,
+            self.dropped_no_coords,
+            self.dropped_out_of_range,
+            self.dropped_bad_timestamp
+        )?;
+        if self.truncated {
+            write!(
+                f,
+                "; the file is truncated, so only the records before the truncation were read"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// records the regions of a record as already resolved, so that looking them up skips the boundary data
+/// the regions apply to every record at exactly the same coordinates
+pub fn preload_regions(record: &Record, regions: BTreeSet<Region>) {
+    PRELOADED_REGIONS
+        .write()
+        .unwrap_or_else(|e| panic!("could not write preloaded regions: {e}"))
+        .insert(record.coordinate_bits(), regions);
+}
+
+/// looks up the timezone at the given coordinates, if the timezone is recognized
+pub fn timezone_at(latitude: f64, longitude: f64) -> Option<Tz> {
+    TIMEZONES
+        .get_tz_name(longitude, latitude)
+        .parse::<Tz>()
+        .ok()
+}
+
+/// areas whose coordinates the boundary data resolves inconsistently, as (south, north, alpha-2 code)
+/// a point within the latitudes of an entry that the boundary data puts in no region is put in the entry's country instead
+/// Antarctica is everything south of 60°S, the area covered by the Antarctic Treaty, including its ice shelves and surrounding sea
+/// other unclaimed or disputed zones are not overridden: a point in Bir Tawil is in no region, the same as the open ocean,
+/// and a point in a disputed area is in whichever regions the boundary data puts it in, which may be several countries at once
+const REGION_OVERRIDES: &[(f64, f64, &str)] = &[(-90.0, -60.0, "AQ")];
+
+/// looks up the regions containing the given coordinates in the boundary data, then applies REGION_OVERRIDES
+/// returns an error if the coordinates are out of range
+pub fn regions_at(latitude: f64, longitude: f64) -> Result<BTreeSet<Region>, String> {
+    let lat_lon = LatLon::new(latitude, longitude).map_err(|e| e.to_string())?;
+    let regions: BTreeSet<Region> = boundaries()
+        .ids(lat_lon)
+        .iter()
+        .map(|code| Region::from_code(code))
+        .collect();
+    if !regions.is_empty() {
+        return Ok(regions);
+    }
+    Ok(REGION_OVERRIDES
+        .iter()
+        .find(|(south, north, _)| (*south..=*north).contains(&latitude))
+        .map(|(_, _, code)| BTreeSet::from([Region::from_code(code)]))
+        .unwrap_or_default())
+}
+
 /// defines the source for a location record
-#[derive(Deserialize, PartialEq, Eq, Hash, Copy, Clone, ValueEnum, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Copy, Clone, ValueEnum, Debug)]
 pub enum Source {
     /// a wifi access point
     Wifi,
@@ -67,7 +407,9 @@ pub enum Source {
     GPS,
     /// cell tower signal
     Cell,
+    /// a semantic marker recorded when arriving at a place, rather than a raw position fix
     VisitArrival,
+    /// a semantic marker recorded when departing a place, rather than a raw position fix
     VisitDeparture,
     /// unknown source (as recorded in Google Takeout)
     Unknown,
@@ -78,11 +420,34 @@ pub enum Source {
 }
 
 impl Source {
+    /// the rank of the source when ordering records at the same instant, with lower values first
+    /// sources that measure position directly are ranked ahead of inferred or unknown ones
+    pub fn priority(&self) -> u8 {
+        match self {
+            Source::GPS => 0,
+            Source::Wifi => 1,
+            Source::Cell => 2,
+            Source::Manual => 3,
+            Source::VisitArrival => 4,
+            Source::VisitDeparture => 5,
+            Source::Unknown => 6,
+            Source::None => 7,
+        }
+    }
+
+    /// whether the source is a semantic or manual marker rather than a raw signal from the device
+    pub fn is_semantic(&self) -> bool {
+        matches!(
+            self,
+            Source::VisitArrival | Source::VisitDeparture | Source::Manual
+        )
+    }
+
     pub fn from_json_source(json: &Option<JsonSource>) -> Self {
         match json {
             Some(s) => match s {
                 JsonSource::Cell => Source::Cell,
-                JsonSource::Unknown => Source::Unknown,
+                JsonSource::Unknown | JsonSource::Other => Source::Unknown,
                 JsonSource::GPS => Source::GPS,
                 JsonSource::Wifi => Source::Wifi,
                 JsonSource::Manual => Source::Manual,
@@ -101,6 +466,10 @@ pub enum Region {
     Obsolete(rust_iso3166::iso3166_3::CountryCode3),
     UnknownCode(String),
     MissingData,
+    /// valid coordinates outside of every region, such as the open ocean
+    InternationalWaters,
+    /// a point that the boundary data puts in several countries at once, such as at a tripoint, sorted by alpha-2 code
+    BorderZone(Vec<rust_iso3166::CountryCode>),
 }
 
 impl Region {
@@ -121,24 +490,126 @@ impl Region {
         }
     }
 
+    /// the ISO code of the region, if it has one
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            Region::CountryCode(c) => Some(c.alpha2),
+            Region::Subdivision(s) => Some(s.code),
+            Region::Obsolete(o) => Some(o.code),
+            Region::UnknownCode(u) => Some(u),
+            Region::MissingData | Region::InternationalWaters | Region::BorderZone(_) => None,
+        }
+    }
+
+    /// the present-day successor of an obsolete region, if it was succeeded by exactly one country
+    /// returns None for regions that are not obsolete, or that were split into several countries
+    pub fn successor(&self) -> Option<rust_iso3166::CountryCode> {
+        match self {
+            Region::Obsolete(o) => match o.new_countries {
+                [successor] => Some(*successor),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     pub fn is_subregion(&self) -> bool {
+        matches!(self, Region::Subdivision(_))
+    }
+
+    /// the country a subdivision belongs to, if this region is a subdivision
+    pub fn parent_country(&self) -> Option<rust_iso3166::CountryCode> {
         match self {
-            Region::Subdivision(_) => true,
-            _ => false,
+            Region::Subdivision(s) => rust_iso3166::from_alpha2(s.country_code),
+            _ => None,
         }
     }
 }
 
-impl Display for Region {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let str = match self {
-            Region::CountryCode(c) => c.name,
+/// if the regions contain only subdivisions and no country, adds the parent country of each subdivision
+/// this allows regions to be compared at country granularity even when the boundary data omits the country
+pub fn with_parent_countries(regions: &BTreeSet<Region>) -> BTreeSet<Region> {
+    if regions.iter().any(|r| matches!(r, Region::CountryCode(_))) {
+        return regions.clone();
+    }
+    let parents = regions
+        .iter()
+        .flat_map(|r| r.parent_country())
+        .map(Region::CountryCode);
+    regions.iter().cloned().chain(parents).collect()
+}
+
+impl Region {
+    /// the rank of the region's kind, for ordering the regions that share a code or have none, in the order of their English names
+    fn kind_rank(&self) -> u8 {
+        match self {
+            Region::CountryCode(_) => 0,
+            Region::Subdivision(_) => 1,
+            Region::Obsolete(_) => 2,
+            Region::UnknownCode(_) => 3,
+            Region::BorderZone(_) => 4,
+            Region::InternationalWaters => 5,
+            Region::MissingData => 6,
+        }
+    }
+}
+
+impl Ord for Region {
+    /// regions are ordered by ISO code, then by kind, then by the codes of a border zone's countries, which doesn't depend on the display language
+    /// this places each country directly before its subdivisions, and the regions without a code (border zones, international waters, missing data) first
+    fn cmp(&self, other: &Self) -> Ordering {
+        let zone_codes = |r: &Self| match r {
+            Region::BorderZone(countries) => countries.iter().map(|c| c.alpha2).collect(),
+            _ => vec![],
+        };
+        self.code()
+            .cmp(&other.code())
+            .then_with(|| self.kind_rank().cmp(&other.kind_rank()))
+            .then_with(|| zone_codes(self).cmp(&zone_codes(other)))
+    }
+}
+
+impl PartialOrd for Region {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Region {
+    /// the name of the region in the language, falling back to the English name from the ISO 3166 dataset where there is no translation
+    pub fn name(&self, language: Language) -> &str {
+        match self {
+            Region::CountryCode(c) => country_name(c.alpha2, language).unwrap_or(c.name),
             Region::Subdivision(s) => s.name,
             Region::Obsolete(o) => o.name,
             Region::UnknownCode(u) => u,
-            Region::MissingData => "Missing Data",
-        };
-        write!(f, "{str}")
+            Region::MissingData => missing_data_name(language),
+            Region::InternationalWaters => international_waters_name(language),
+            Region::BorderZone(_) => border_zone_name(language),
+        }
+    }
+}
+
+impl Display for Region {
+    /// displays the region's name, in the language chosen for the run
+    /// the alternate form (`{:#}`) appends the ISO code, e.g. "California (US-CA)"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = self.name(language());
+        if let Region::BorderZone(countries) = self {
+            let names = countries
+                .iter()
+                .map(localized_country_name)
+                .collect::<Vec<&str>>()
+                .join("/");
+            return write!(f, "{str}: {names}");
+        }
+        match self.code() {
+            // an unknown code is already displayed as its code
+            Some(code) if f.alternate() && !matches!(self, Region::UnknownCode(_)) => {
+                write!(f, "{str} ({code})")
+            }
+            _ => write!(f, "{str}"),
+        }
     }
 }
 
@@ -146,7 +617,52 @@ impl Display for Region {
 #[derive(Clone, Debug)]
 pub struct BorderCrossing {
     pub timestamp: DateTime<Utc>,
-    pub new_regions: HashSet<Region>,
+    pub new_regions: BTreeSet<Region>,
+    /// the (latitude, longitude) of the record that triggered the crossing
+    /// synthetic crossings such as missing data have no coordinates
+    pub coordinates: Option<(f64, f64)>,
+    /// why detection emitted the crossing
+    pub reason: CrossingReason,
+    /// the source of the record that triggered the crossing
+    /// synthetic crossings such as missing data have Source::None
+    pub source: Source,
+    /// the flight from the Semantic Location History that the crossing was made during or just after, which corroborates it as a real move
+    /// set by corroborate_crossings; None if no flights were read
+    pub flight: Option<Flight>,
+}
+
+/// the reason a crossing was emitted during detection, for explaining the output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CrossingReason {
+    /// the first record, which has nothing to be compared to
+    FirstRecord,
+    /// the record entered regions the previous record was not in
+    #[default]
+    RegionChange,
+    /// the records on either side of it were at least the missing data threshold apart
+    Gap,
+    /// the first record after missing data, which resolves where the location was once data resumes
+    PostGap,
+}
+
+impl Display for CrossingReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            CrossingReason::FirstRecord => "first-record",
+            CrossingReason::RegionChange => "region-change",
+            CrossingReason::Gap => "gap>threshold",
+            CrossingReason::PostGap => "post-gap-resolution",
+        };
+        write!(f, "{str}")
+    }
+}
+
+impl BorderCrossing {
+    /// the timezone at the crossing's coordinates, if it has coordinates and the timezone is recognized
+    pub fn local_timezone(&self) -> Option<Tz> {
+        let (latitude, longitude) = self.coordinates?;
+        timezone_at(latitude, longitude)
+    }
 }
 
 impl Display for BorderCrossing {
@@ -168,6 +684,10 @@ impl From<&Record> for BorderCrossing {
         BorderCrossing {
             timestamp: record.timestamp,
             new_regions: record.regions(),
+            coordinates: Some((record.latitude, record.longitude)),
+            reason: CrossingReason::default(),
+            source: record.source,
+            flight: None,
         }
     }
 }
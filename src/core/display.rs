@@ -0,0 +1,272 @@
+// this file contains the rendering of border crossings as human-readable text
+
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::FixedOffset;
+use clap::ValueEnum;
+
+use crate::core::data::{BorderCrossing, Region};
+use crate::core::visits::{ordinal, visit_numbers};
+
+/// the order in which border crossings are listed
+#[derive(PartialEq, Eq, Copy, Clone, Default, ValueEnum, Debug)]
+pub enum SortOrder {
+    /// oldest first
+    #[default]
+    Asc,
+    /// newest first
+    Desc,
+}
+
+/// options controlling how border crossings are rendered as text
+#[derive(Default)]
+pub struct DisplayOptions {
+    /// render timestamps in the timezone of the crossing's coordinates, falling back to UTC when there are none
+    pub local_time: bool,
+    /// render timestamps at this fixed offset from UTC, when not rendering in local time
+    pub utc_offset: Option<FixedOffset>,
+    /// annotate each country entry as a first entry or a re-entry
+    pub show_visit_count: bool,
+    /// render the ISO code of each region after its name
+    pub show_codes: bool,
+    /// prefix each country with its flag emoji
+    pub flags: bool,
+    /// render the coordinates of the record that triggered each crossing
+    pub show_coords: bool,
+    /// render the source of the record that triggered each crossing
+    pub show_source: bool,
+    /// render a subdivision on the same line as its parent country, as "Country / Subdivision", when both are in the crossing
+    pub combine_subdivisions: bool,
+    /// annotate each crossing with the reason detection emitted it
+    pub explain: bool,
+}
+
+/// which of a chronological list of crossings are output, and in which order, shared by every format so that they list the same crossings
+#[derive(Clone, Copy, Default)]
+pub struct Listing<'a> {
+    /// the order to list the crossings in
+    pub sort: SortOrder,
+    /// list at most this many crossings, in the order they are listed
+    pub limit: Option<usize>,
+    /// the crossing chronologically after the last of the crossings, when they are a slice of a longer list such as one year of it
+    pub following: Option<&'a BorderCrossing>,
+}
+
+/// a crossing as it is listed, with the chronologically next crossing that its duration runs until
+/// the next crossing is known even when it isn't listed itself, such as when it is past the limit or in the following slice
+pub struct ListedCrossing<'a> {
+    /// the index of the crossing in the chronological list it was listed from
+    pub index: usize,
+    pub crossing: &'a BorderCrossing,
+    pub next: Option<&'a BorderCrossing>,
+}
+
+impl ListedCrossing<'_> {
+    /// the whole days from the crossing until the next crossing; None for the final crossing
+    pub fn duration_days(&self) -> Option<i64> {
+        self.next
+            .map(|next| (next.timestamp - self.crossing.timestamp).num_days())
+    }
+}
+
+impl<'a> Listing<'a> {
+    /// the crossings to list, sorted and then limited
+    /// requires crossings to be sorted by timestamp, whatever order they are listed in
+    pub fn list(&self, crossings: &'a [BorderCrossing]) -> Vec<ListedCrossing<'a>> {
+        let mut listed: Vec<ListedCrossing> = crossings
+            .iter()
+            .enumerate()
+            .map(|(index, crossing)| ListedCrossing {
+                index,
+                crossing,
+                next: crossings.get(index + 1).or(self.following),
+            })
+            .collect();
+        if self.sort == SortOrder::Desc {
+            listed.reverse();
+        }
+        if let Some(limit) = self.limit {
+            listed.truncate(limit);
+        }
+        listed
+    }
+}
+
+pub fn timestamp_to_string(crossing: &BorderCrossing, options: &DisplayOptions) -> String {
+    // the timezone data is slow to load, so it is only looked up when local time is requested
+    let local_timezone = if options.local_time {
+        crossing.local_timezone()
+    } else {
+        None
+    };
+    match local_timezone {
+        Some(tz) => format!(
+            "{} ({tz})",
+            crossing.timestamp.with_timezone(&tz).to_rfc2822()
+        ),
+        None => match options.utc_offset {
+            Some(offset) => crossing.timestamp.with_timezone(&offset).to_rfc2822(),
+            None => crossing.timestamp.to_rfc2822(),
+        },
+    }
+}
+
+/// the flag emoji of a country, built from the regional indicator symbols of its alpha-2 code
+/// terminals without flag support render the symbols as the two letters of the code, so the output stays readable
+/// returns an empty string if the code is not two ASCII letters
+pub fn flag_emoji(alpha2: &str) -> String {
+    if alpha2.len() != 2 || !alpha2.chars().all(|c| c.is_ascii_alphabetic()) {
+        return String::new();
+    }
+    alpha2
+        .to_ascii_uppercase()
+        .chars()
+        .filter_map(|c| char::from_u32(0x1F1E6 + (c as u32 - 'A' as u32)))
+        .collect()
+}
+
+/// the parent country of a subdivision, if it is also one of the given regions
+fn parent_in(region: &Region, regions: &BTreeSet<Region>) -> Option<Region> {
+    region
+        .parent_country()
+        .map(Region::CountryCode)
+        .filter(|p| regions.contains(p))
+}
+
+/// renders the name of a region, with its code and flag if requested
+fn region_name(region: &Region, options: &DisplayOptions) -> String {
+    let name = if options.show_codes {
+        format!("{region:#}")
+    } else {
+        region.to_string()
+    };
+    match region {
+        Region::CountryCode(c) if options.flags => format!("{} {name}", flag_emoji(c.alpha2)),
+        _ => name,
+    }
+}
+
+/// renders a region as a line of a crossing's block
+/// subdivisions are nested under their parent country when it is also one of the crossing's regions,
+/// or prefixed by it on the same line when combining subdivisions
+pub fn region_to_string(
+    region: &Region,
+    regions: &BTreeSet<Region>,
+    visit_number: Option<&usize>,
+    options: &DisplayOptions,
+) -> String {
+    let parent = parent_in(region, regions);
+    let (indent, name) = match parent {
+        Some(p) if options.combine_subdivisions => (
+            "",
+            format!(
+                "{} / {}",
+                region_name(&p, options),
+                region_name(region, options)
+            ),
+        ),
+        Some(_) => ("    ", region_name(region, options)),
+        None => ("", region_name(region, options)),
+    };
+    match visit_number {
+        Some(1) => format!("    | {indent}{name} (first entry)"),
+        Some(n) => format!("    | {indent}{name} (re-entry, {} visit)", ordinal(*n)),
+        None => format!("    | {indent}{name}"),
+    }
+}
+
+pub fn border_crossing_to_string(
+    crossing: &BorderCrossing,
+    next_crossing: &Option<&BorderCrossing>,
+    visit_numbers: Option<&HashMap<Region, usize>>,
+    options: &DisplayOptions,
+) -> String {
+    let timestamp_str = timestamp_to_string(crossing, options);
+    let regions = &crossing.new_regions;
+    let region_strings: String = regions
+        .iter()
+        // when combining, a country is rendered as part of its subdivisions' lines rather than on its own
+        .filter(|r| {
+            !options.combine_subdivisions
+                || !regions
+                    .iter()
+                    .any(|s| parent_in(s, regions) == Some((*r).clone()))
+        })
+        .map(|r| {
+            // a combined line carries the visit number of its country
+            let visit_region = parent_in(r, regions)
+                .filter(|_| options.combine_subdivisions)
+                .unwrap_or_else(|| r.clone());
+            region_to_string(
+                r,
+                regions,
+                visit_numbers.and_then(|v| v.get(&visit_region)),
+                options,
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let duration_string = match next_crossing {
+        Some(next) => {
+            let days = (next.timestamp - crossing.timestamp).num_days();
+            format!("    | Duration: {days} Days")
+        }
+        None => "    | Duration Unknown".to_string(),
+    };
+    let mut lines = vec![timestamp_str.as_str(), "    |", &region_strings];
+    // crossings without coordinates, such as missing data, have no line rather than an empty one
+    let coords_string = crossing
+        .coordinates
+        .filter(|_| options.show_coords)
+        .map(|(latitude, longitude)| format!("    | ({latitude}, {longitude})"));
+    if let Some(coords) = &coords_string {
+        lines.push(coords);
+    }
+    // likewise, synthetic crossings weren't triggered by a record, so they have no source line
+    let source_string = format!("    | Source: {:?}", crossing.source);
+    if options.show_source && crossing.coordinates.is_some() {
+        lines.push(&source_string);
+    }
+    let reason_string = format!("    | Reason: {}", crossing.reason);
+    if options.explain {
+        lines.push(&reason_string);
+    }
+    let flight_string = crossing
+        .flight
+        .as_ref()
+        .map(|f| format!("    | Flight: {f}"));
+    if let Some(flight) = &flight_string {
+        lines.push(flight);
+    }
+    lines.extend([duration_string.as_str(), "    |\n"]);
+    lines.join("\n")
+}
+
+/// requires crossings to be sorted by timestamp, whatever order they are listed in
+/// each crossing's duration is always the time until the chronologically next crossing, even when listed newest first
+/// the crossings left out by the limit still count as the next crossing of those listed, and a note of how many were left out follows the list
+pub fn display_border_crossings(
+    crossings: &[BorderCrossing],
+    listing: &Listing,
+    options: &DisplayOptions,
+) -> String {
+    let visits = if options.show_visit_count {
+        visit_numbers(crossings)
+    } else {
+        vec![]
+    };
+    let listed = listing.list(crossings);
+    let mut strings: Vec<String> = listed
+        .iter()
+        .map(|l| border_crossing_to_string(l.crossing, &l.next, visits.get(l.index), options))
+        .collect();
+    if listed.len() < crossings.len() {
+        strings.push(format!(
+            "(showing the first {} of {} crossings)\n",
+            listed.len(),
+            crossings.len()
+        ));
+    }
+
+    strings.concat()
+}
@@ -0,0 +1,141 @@
+// this file contains the health report of an export, for auditing it before trusting an analysis
+
+use std::fmt::{self, Display};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+
+use crate::core::data::{ParseStats, Record, Source, MISSING_DATA_THRESHOLD};
+use crate::core::input::InputFormat;
+
+/// a summary of the contents and quality of an export, computed without detecting crossings
+#[derive(Debug)]
+pub struct HealthReport {
+    pub format: InputFormat,
+    pub stats: ParseStats,
+    pub first_timestamp: Option<DateTime<Utc>>,
+    pub last_timestamp: Option<DateTime<Utc>>,
+    /// the number of kept records from each source, in the order the sources are declared
+    pub sources: Vec<(Source, usize)>,
+    /// the number of gaps between consecutive records of at least the missing data threshold
+    pub gaps: usize,
+}
+
+impl HealthReport {
+    /// requires records to be sorted by timestamp
+    pub fn new(format: InputFormat, records: &[Record], stats: ParseStats) -> Self {
+        let mut sources: Vec<(Source, usize)> =
+            Source::value_variants().iter().map(|s| (*s, 0)).collect();
+        let mut gaps = 0;
+        for (i, record) in records.iter().enumerate() {
+            if let Some((_, count)) = sources.iter_mut().find(|(s, _)| *s == record.source) {
+                *count += 1;
+            }
+            let is_gap = i
+                .checked_sub(1)
+                .and_then(|j| records.get(j))
+                .is_some_and(|prev| record.timestamp - prev.timestamp >= MISSING_DATA_THRESHOLD);
+            if is_gap {
+                gaps += 1;
+            }
+        }
+        HealthReport {
+            format,
+            stats,
+            first_timestamp: records.first().map(|r| r.timestamp),
+            last_timestamp: records.last().map(|r| r.timestamp),
+            sources,
+            gaps,
+        }
+    }
+
+    /// the number of kept records without a recorded source
+    pub fn without_source(&self) -> usize {
+        self.sources
+            .iter()
+            .find(|(s, _)| *s == Source::None)
+            .map_or(0, |(_, n)| *n)
+    }
+}
+
+/// the percentage of records without a source at or above which the report notes that the export may be of low quality
+pub const LOW_QUALITY_NO_SOURCE_PERCENT: f64 = 25.0;
+
+/// the share of n in total as a percentage, or 0 if there is no total
+fn percent(n: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        n as f64 * 100.0 / total as f64
+    }
+}
+
+impl Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let timestamp_str = |t: Option<DateTime<Utc>>| match t {
+            Some(t) => t.to_rfc2822(),
+            None => "Unknown".to_string(),
+        };
+        let days_covered = match (self.first_timestamp, self.last_timestamp) {
+            (Some(first), Some(last)) => (last - first).num_days().to_string(),
+            _ => "Unknown".to_string(),
+        };
+        let stats = &self.stats;
+        let with_coords = stats.total - stats.dropped_no_coords;
+        let mut rows = vec![
+            ("Format", self.format.to_string()),
+            ("Total Records", stats.total.to_string()),
+            ("Usable Records", stats.kept.to_string()),
+            (
+                "With Coordinates",
+                format!("{with_coords} ({:.1}%)", percent(with_coords, stats.total)),
+            ),
+            (
+                "Invalid Coordinates",
+                stats.dropped_out_of_range.to_string(),
+            ),
+            (
+                "Invalid Timestamps",
+                stats.dropped_bad_timestamp.to_string(),
+            ),
+            (
+                "Truncated",
+                if stats.truncated { "Yes" } else { "No" }.to_string(),
+            ),
+            ("First Record", timestamp_str(self.first_timestamp)),
+            ("Last Record", timestamp_str(self.last_timestamp)),
+            ("Days Covered", days_covered),
+            ("Gaps Over 1 Day", self.gaps.to_string()),
+        ];
+        // records without a source are often interpolated, so a large share of them makes odd results more likely
+        let without_source = self.without_source();
+        let without_source_percent = percent(without_source, stats.kept);
+        let note = if without_source_percent >= LOW_QUALITY_NO_SOURCE_PERCENT {
+            "; likely a low-quality export, consider --exclude-source none"
+        } else {
+            ""
+        };
+        rows.push((
+            "Without Source",
+            format!("{without_source} ({without_source_percent:.1}%){note}"),
+        ));
+        let sources = self
+            .sources
+            .iter()
+            .filter(|(_, n)| *n > 0)
+            .map(|(source, n)| {
+                (
+                    "",
+                    format!("{source:?}: {n} ({:.1}%)", percent(*n, stats.kept)),
+                )
+            });
+        rows.push(("Sources", String::new()));
+        rows.extend(sources);
+        let table = rows
+            .iter()
+            .map(|(label, value)| format!("{label:<22}{value}").trim_end().to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        write!(f, "{table}")
+    }
+}
@@ -0,0 +1,657 @@
+// this file contains the reading of records from Google Takeout exports
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::{ffi::OsStr, fs, path::PathBuf};
+
+use chrono::{NaiveDate, TimeDelta};
+use flate2::read::{DeflateDecoder, GzDecoder};
+use log::{info, warn};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::core::cache::{records_from_cache, CACHE_SUFFIX};
+use crate::core::data::{ParseStats, Record, RecordError, Source};
+use crate::core::json::JsonRecord;
+
+/// reads and merges the records from each of the given files
+/// the merged records are sorted chronologically, and duplicate records within or across files are removed
+/// records with out-of-range coordinates are counted as dropped in the parse stats rather than aborting the whole run
+pub fn read_records_from_files(paths: &[PathBuf]) -> Vec<Record> {
+    read_records_with_stats(paths, &HashSet::new()).0
+}
+
+/// reads and merges the records from each of the given files, as with read_records_from_files
+/// also returns the parse stats of each file, in the same order as the paths
+/// records from the excluded sources are dropped before duplicates are removed, so that they can't displace the records of another source at the same instant
+/// each file is read and parsed on its own thread, since large exports are slow to parse
+pub fn read_records_with_stats(
+    paths: &[PathBuf],
+    excluded_sources: &HashSet<Source>,
+) -> (Vec<Record>, Vec<ParseStats>) {
+    let file_results: Vec<(Vec<Record>, ParseStats)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = paths
+            .iter()
+            .map(|path| scope.spawn(move || read_records_from_file(path)))
+            .collect();
+        // the reason for a failure was already printed by the thread that panicked
+        handles
+            .into_iter()
+            .zip(paths)
+            .map(|(handle, path)| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| panic!("could not read records from {path:?}"))
+            })
+            .collect()
+    });
+
+    let mut records: Vec<Record> = vec![];
+    let mut file_stats: Vec<ParseStats> = vec![];
+    for (path, (file_records, stats)) in paths.iter().zip(file_results) {
+        file_stats.push(stats);
+        // dropped records, truncation, and empty files are worth surfacing by default, since they silently shrink the results
+        if stats.dropped() > 0 || stats.truncated || stats.empty {
+            warn!("{path:?}: {stats}");
+        } else {
+            info!("{path:?}: {stats}");
+        }
+        records.extend(file_records);
+    }
+    if !excluded_sources.is_empty() {
+        let record_count = records.len();
+        records.retain(|r| !excluded_sources.contains(&r.source));
+        info!(
+            "excluded {} records by source",
+            record_count - records.len()
+        );
+    }
+    sort_and_dedup_records(&mut records);
+    (records, file_stats)
+}
+
+/// sorts records chronologically, then keeps only one record per timestamp
+/// of the records sharing a timestamp, the one with the highest priority source is kept, so a coarse cell fix can't contradict a gps fix taken at the same instant
+/// this also removes exact duplicates, which would otherwise produce zero-length segments, and overlapping exports are full of them
+pub fn sort_and_dedup_records(records: &mut Vec<Record>) {
+    records.sort_by(Record::cmp_chronological);
+    let record_count = records.len();
+    // records at the same instant are adjacent and ordered by source priority, so the first of each run is the one to keep
+    records.dedup_by_key(|r| r.timestamp);
+    info!(
+        "skipped {} records sharing a timestamp with a better source",
+        record_count - records.len()
+    );
+}
+
+/// the distance in meters within which records close together in time are considered stationary jitter when thinning
+pub const THINNING_RADIUS_M: f64 = 25.0;
+
+/// thins out rapid fixes at the same spot, keeping at most one record per window while stationary
+/// a record is dropped if it is within window of the last kept record and within THINNING_RADIUS_M of it
+/// records that have moved further than that are always kept, so movement is never collapsed
+/// requires records to be sorted by timestamp
+pub fn thin_records(records: &[Record], window: TimeDelta) -> Vec<Record> {
+    let mut thinned: Vec<Record> = vec![];
+    for record in records {
+        let is_jitter = thinned.last().is_some_and(|kept| {
+            record.timestamp - kept.timestamp < window
+                && kept.distance_km(record) * 1000.0 <= THINNING_RADIUS_M
+        });
+        if !is_jitter {
+            thinned.push(*record);
+        }
+    }
+    thinned
+}
+
+/// drops every record from any calendar day, in UTC, with fewer than min_records records
+/// a day with a lone stray fix, often a bad cell tower position, can otherwise fabricate a whole crossing
+pub fn drop_sparse_days(records: &[Record], min_records: usize) -> Vec<Record> {
+    let mut counts: HashMap<NaiveDate, usize> = HashMap::new();
+    for record in records {
+        *counts.entry(record.timestamp.date_naive()).or_default() += 1;
+    }
+    records
+        .iter()
+        .filter(|r| counts[&r.timestamp.date_naive()] >= min_records)
+        .copied()
+        .collect()
+}
+
+/// whether an archive entry is the location history's Records.json
+/// entries are matched by file name, since the folders within a Takeout archive vary
+fn is_records_json(entry_name: &str) -> bool {
+    Path::new(entry_name).file_name() == Some(OsStr::new("Records.json"))
+}
+
+/// whether the path is a gzipped tar archive, by its .tgz or .tar.gz extension
+pub fn is_tar_gz(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".tgz") || name.ends_with(".tar.gz"))
+}
+
+/// reads a document, such as Records.json from within an archive, to the end
+fn read_to_bytes(mut reader: impl Read) -> Vec<u8> {
+    let mut buf: Vec<u8> = Vec::new();
+    reader
+        .read_to_end(&mut buf)
+        .unwrap_or_else(|e| panic!("could not read data: {e}"));
+    buf
+}
+
+/// reads a document, such as a cache, as a utf-8 string
+fn read_to_string(reader: impl Read) -> String {
+    String::from_utf8(read_to_bytes(reader))
+        .unwrap_or_else(|e| panic!("could not read data as utf-8 string: {e}"))
+}
+
+/// whether the path is a cache of records written by records_to_cache, by its file name suffix
+fn is_cache(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(CACHE_SUFFIX))
+}
+
+/// how many levels of folders below a directory are searched for an extracted Records.json
+/// an extracted export has it at Takeout/<Location History folder>/Records.json, and the folder name is localized
+const RECORDS_JSON_SEARCH_DEPTH: usize = 3;
+
+/// expands each path that is a directory, or a glob of file names such as takeout-*.zip, into the archives it covers that contain Records.json
+/// Takeout splits large exports into several archives and Records.json can land in any of them, so the archives without it are skipped
+/// a directory that is, or contains, an extracted export is instead expanded to its Records.json, which is preferred over any archives beside it
+/// other paths are kept as-is
+/// panics if a directory or glob covers no archive containing Records.json
+pub fn expand_paths(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut expanded: Vec<PathBuf> = vec![];
+    for path in paths {
+        let candidates: Vec<PathBuf> = if path.is_dir() {
+            if let Some(records_json) = find_records_json(path, RECORDS_JSON_SEARCH_DEPTH) {
+                info!("reading extracted {records_json:?}");
+                expanded.push(records_json);
+                continue;
+            }
+            list_dir(path).into_iter().collect()
+        } else if is_glob(path) {
+            let dir = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            let pattern = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            list_dir(dir)
+                .into_iter()
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|name| matches_glob(pattern, name))
+                })
+                .collect()
+        } else {
+            expanded.push(path.clone());
+            continue;
+        };
+        let archives: Vec<PathBuf> = candidates
+            .into_iter()
+            .filter(|p| p.extension() == Some(OsStr::new("zip")) || is_tar_gz(p))
+            .filter(|p| {
+                let found = contains_records_json(p);
+                if !found {
+                    info!("skipped {p:?}, which does not contain Records.json");
+                }
+                found
+            })
+            .collect();
+        if archives.is_empty() {
+            panic!("could not find Records.json in any archive in {path:?}");
+        }
+        expanded.extend(archives);
+    }
+    expanded
+}
+
+/// the first Records.json within the directory or the folders below it, down to the given depth
+/// folders are searched in order of name, and a folder's own Records.json is found before those of the folders within it
+fn find_records_json(dir: &Path, depth: usize) -> Option<PathBuf> {
+    let records_json = dir.join("Records.json");
+    if records_json.is_file() {
+        return Some(records_json);
+    }
+    if depth == 0 {
+        return None;
+    }
+    let mut subdirs: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("could not read directory {dir:?}: {e}"))
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .collect();
+    subdirs.sort();
+    subdirs
+        .iter()
+        .find_map(|subdir| find_records_json(subdir, depth - 1))
+}
+
+/// the files in a directory, sorted by name so that the parts of a split export are read in order
+fn list_dir(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("could not read directory {dir:?}: {e}"))
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+    files
+}
+
+/// whether the file name of the path is a glob pattern, containing * or ?
+fn is_glob(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.contains(['*', '?']))
+}
+
+/// whether the name matches the glob pattern, where * matches any run of characters and ? matches any one character
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // matched[j] is whether the pattern so far matches the first j characters of the name
+    let mut matched = vec![false; name.len() + 1];
+    matched[0] = true;
+    for p in pattern {
+        let previous = matched.clone();
+        matched[0] = p == '*' && previous[0];
+        for j in 1..=name.len() {
+            matched[j] = match p {
+                '*' => previous[j] || matched[j - 1],
+                '?' => previous[j - 1],
+                c => previous[j - 1] && c == name[j - 1],
+            };
+        }
+    }
+    matched[name.len()]
+}
+
+/// whether the archive contains a Records.json entry
+/// the entries of a zip are listed without decompressing them, but a tar has to be read through
+fn contains_records_json(path: &Path) -> bool {
+    let file = fs::File::open(path).unwrap_or_else(|e| panic!("could not open {path:?}: {e}"));
+    let reader = std::io::BufReader::new(file);
+    if is_tar_gz(path) {
+        tar::Archive::new(GzDecoder::new(reader))
+            .entries()
+            .unwrap_or_else(|e| panic!("could not read archive {path:?}: {e}"))
+            .filter_map(|entry| entry.ok())
+            .any(|entry| {
+                entry
+                    .path()
+                    .is_ok_and(|p| p.to_str().is_some_and(is_records_json))
+            })
+    } else {
+        ZipArchive::new(reader)
+            .unwrap_or_else(|e| panic!("could not read archive {path:?}: {e}"))
+            .file_names()
+            .any(is_records_json)
+    }
+}
+
+/// the kind of file records are read from, as detected from its path
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum InputFormat {
+    /// json read from standard input
+    Stdin,
+    /// a cache written by records_to_cache
+    Cache,
+    /// a zip archive containing Records.json
+    Zip,
+    /// a gzipped tar archive containing Records.json
+    TarGz,
+    /// the Records.json document itself
+    Json,
+}
+
+impl Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            InputFormat::Stdin => "JSON (stdin)",
+            InputFormat::Cache => "Record cache",
+            InputFormat::Zip => "Zip archive",
+            InputFormat::TarGz => "Gzipped tar archive",
+            InputFormat::Json => "JSON",
+        };
+        write!(f, "{str}")
+    }
+}
+
+/// detects the format of a file from its path
+/// panics if the file type isn't recognized
+pub fn detect_format(path: &Path) -> InputFormat {
+    if path.as_os_str() == "-" {
+        InputFormat::Stdin
+    } else if path.is_dir() {
+        panic!("could not handle directory {path:?}, which must first be expanded to the Records.json or archives within it");
+    } else if is_cache(path) {
+        InputFormat::Cache
+    } else if path.extension() == Some(OsStr::new("zip")) {
+        InputFormat::Zip
+    } else if is_tar_gz(path) {
+        InputFormat::TarGz
+    } else if path.extension() == Some(OsStr::new("json")) {
+        InputFormat::Json
+    } else {
+        let ext = path.extension().unwrap_or_default().to_string_lossy();
+        panic!("could not handle unknown filetype, must be one of {{.zip, .tgz, .tar.gz, .json}}: {ext}");
+    }
+}
+
+/// reads the records of a file, picking the reader from the format of its path
+/// a zip is read from the file in place, since an archive with the rest of a Takeout export can be far too large to buffer
+pub fn read_records_from_file(path: &PathBuf) -> (Vec<Record>, ParseStats) {
+    let format = detect_format(path);
+    if format == InputFormat::Stdin {
+        // an archive can't be read from a stream, so stdin is always json
+        return collect_records(records_from_reader(std::io::stdin().lock(), format));
+    }
+    let file = fs::File::open(path).unwrap_or_else(|e| panic!("could not open {path:?}: {e}"));
+    let reader = BufReader::new(file);
+    collect_records(match format {
+        InputFormat::Zip => RecordReader::Document(RecordStream::new(records_json_in_zip(
+            reader,
+            &format!("{path:?}"),
+        ))),
+        _ => records_from_reader(reader, format),
+    })
+}
+
+/// the records from any source of data in the given format, yielded as they are parsed
+/// Records.json is parsed incrementally, even from within an archive, so that only one raw record is held in memory at a time
+/// a zip has to be seekable, so it is buffered in memory first, though the Records.json within it is not; a cache is read whole
+pub fn records_from_reader<'a>(reader: impl Read + 'a, format: InputFormat) -> RecordReader<'a> {
+    match format {
+        InputFormat::Json | InputFormat::Stdin => {
+            RecordReader::Document(RecordStream::new(Box::new(reader)))
+        }
+        InputFormat::Zip => RecordReader::Document(RecordStream::new(records_json_in_zip(
+            Cursor::new(read_to_bytes(reader)),
+            "zip archive",
+        ))),
+        InputFormat::TarGz => RecordReader::Document(RecordStream::new(records_json_in_tar(
+            GzDecoder::new(reader),
+        ))),
+        InputFormat::Cache => {
+            // a cache holds records that were already parsed and validated, so none are dropped
+            let records = records_from_cache(&read_to_string(reader));
+            let stats = ParseStats {
+                total: records.len(),
+                kept: records.len(),
+                ..Default::default()
+            };
+            RecordReader::Cache(records.into_iter(), stats)
+        }
+    }
+}
+
+/// the records of a source of data in any format, as returned by records_from_reader
+/// the stats count the raw records read so far, so they are complete once the records run out
+pub enum RecordReader<'a> {
+    /// a Records.json document, parsed as it is read
+    Document(RecordStream<Box<dyn Read + 'a>>),
+    /// the records of a cache, which was read whole, with its stats
+    Cache(std::vec::IntoIter<Record>, ParseStats),
+}
+
+impl RecordReader<'_> {
+    pub fn stats(&self) -> ParseStats {
+        match self {
+            RecordReader::Document(stream) => stream.stats(),
+            RecordReader::Cache(_, stats) => *stats,
+        }
+    }
+}
+
+impl Iterator for RecordReader<'_> {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Record> {
+        match self {
+            RecordReader::Document(stream) => stream.next(),
+            RecordReader::Cache(records, _) => records.next(),
+        }
+    }
+}
+
+/// the data of Records.json within a zip archive, decompressed as it is read
+/// the entry is located through the archive, then read from the archive's reader directly, so that it doesn't borrow the archive
+/// name identifies the archive in errors
+fn records_json_in_zip<'a, R: Read + Seek + 'a>(reader: R, name: &str) -> Box<dyn Read + 'a> {
+    let mut archive = ZipArchive::new(reader)
+        .unwrap_or_else(|e| panic!("could not read zip archive {name}: {e}"));
+    let entry_name = archive
+        .file_names()
+        .find(|entry_name| is_records_json(entry_name))
+        .map(|entry_name| entry_name.to_owned())
+        .unwrap_or_else(|| panic!("could not find Records.json in {name}"));
+    let (data_start, compressed_size, compression) = {
+        let entry = archive
+            .by_name(&entry_name)
+            .unwrap_or_else(|e| panic!("could not extract data from Records.json: {e}"));
+        (
+            entry.data_start(),
+            entry.compressed_size(),
+            entry.compression(),
+        )
+    };
+    let mut reader = archive.into_inner();
+    reader
+        .seek(SeekFrom::Start(data_start))
+        .unwrap_or_else(|e| panic!("could not extract data from Records.json: {e}"));
+    let data = reader.take(compressed_size);
+    match compression {
+        CompressionMethod::Stored => Box::new(data),
+        CompressionMethod::Deflated => Box::new(DeflateDecoder::new(data)),
+        method => panic!("could not extract data from Records.json compressed with {method}"),
+    }
+}
+
+/// the data of Records.json within a tar archive
+/// entries of a tar can only be read in order, so this streams through the decompressed archive until Records.json
+/// the archive is then unwrapped at the start of the entry's data, so that the data doesn't borrow the archive
+fn records_json_in_tar<'a, R: Read + 'a>(reader: R) -> Box<dyn Read + 'a> {
+    let mut archive = tar::Archive::new(CountingReader {
+        reader,
+        position: 0,
+    });
+    let (data_start, size) = archive
+        .entries()
+        .unwrap_or_else(|e| panic!("could not read tar archive: {e}"))
+        .filter_map(|entry| entry.ok())
+        .find(|entry| {
+            entry
+                .path()
+                .is_ok_and(|p| p.to_str().is_some_and(is_records_json))
+        })
+        .map(|entry| (entry.raw_file_position(), entry.size()))
+        .unwrap_or_else(|| panic!("could not find Records.json in tar archive"));
+    let mut reader = archive.into_inner();
+    // the entry's data is only read on demand, so the archive has read no further than the start of it
+    let skipped = data_start - reader.position;
+    std::io::copy(&mut reader.by_ref().take(skipped), &mut std::io::sink())
+        .unwrap_or_else(|e| panic!("could not read tar archive: {e}"));
+    Box::new(reader.take(size))
+}
+
+/// a reader that counts the bytes read through it, to find where within its data it is
+struct CountingReader<R: Read> {
+    reader: R,
+    position: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+/// reads all of the records of a reader, along with its stats
+fn collect_records(mut reader: RecordReader) -> (Vec<Record>, ParseStats) {
+    let records: Vec<Record> = reader.by_ref().collect();
+    (records, reader.stats())
+}
+
+/// where a RecordStream is within its document
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum StreamPosition {
+    /// before the locations array, which is found on the first read
+    Start,
+    /// within the locations array, between records
+    Locations,
+    /// past the end of the locations array, or of the data if it was truncated
+    End,
+}
+
+/// an iterator over the records of a Records.json document, parsing one raw record at a time as it is read
+/// the records that can't be used are dropped, and counted in the stats
+/// a document that ends early, such as from an interrupted download, still yields the records before the truncation
+/// panics if the document isn't a Records.json
+pub struct RecordStream<R: Read> {
+    reader: BufReader<R>,
+    position: StreamPosition,
+    stats: ParseStats,
+}
+
+impl<R: Read> RecordStream<R> {
+    pub fn new(reader: R) -> Self {
+        RecordStream {
+            reader: BufReader::new(reader),
+            position: StreamPosition::Start,
+            stats: ParseStats::default(),
+        }
+    }
+
+    /// the counts of the raw records read so far
+    pub fn stats(&self) -> ParseStats {
+        ParseStats {
+            kept: self.stats.total - self.stats.dropped(),
+            ..self.stats
+        }
+    }
+
+    /// the next byte of the data that isn't whitespace, or None at the end of the data
+    fn next_token(&mut self) -> Option<u8> {
+        let mut byte = [0u8];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) => return None,
+                Ok(_) if byte[0].is_ascii_whitespace() => continue,
+                Ok(_) => return Some(byte[0]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => panic!("could not read data: {e}"),
+            }
+        }
+    }
+
+    /// deserializes the value that starts with the given byte, which was already read, and the data after it
+    /// the deserializer reads a byte at a time and stops at the end of an object or string, so the data after them is left unread
+    /// after a number or literal it has also read the byte that ends it, which is at most a separator between keys that isn't needed
+    fn deserialize<T: DeserializeOwned>(&mut self, first: u8) -> serde_json::Result<T> {
+        let first = [first];
+        T::deserialize(&mut serde_json::Deserializer::from_reader(
+            first.as_slice().chain(&mut self.reader),
+        ))
+    }
+
+    /// reads up to the start of the locations array, skipping the values of any other keys
+    /// returns false if the data ends first; data without even the start of a document is marked as empty rather than truncated
+    fn find_locations(&mut self) -> bool {
+        match self.next_token() {
+            Some(b'{') => {}
+            None => {
+                self.stats.empty = true;
+                return false;
+            }
+            Some(b) => panic!("could not deserialize json: unexpected {:?}", b as char),
+        }
+        loop {
+            match self.next_token() {
+                None => return false,
+                Some(b',') => continue,
+                Some(b'}') => panic!("could not deserialize json: missing field `locations`"),
+                Some(b'"') => {}
+                Some(b) => panic!("could not deserialize json: unexpected {:?}", b as char),
+            }
+            let key: String = match self.deserialize(b'"') {
+                Ok(key) => key,
+                Err(e) if e.is_eof() => return false,
+                Err(e) => panic!("could not deserialize json: {e}"),
+            };
+            if self.next_token() != Some(b':') {
+                return false;
+            }
+            let Some(first) = self.next_token() else {
+                return false;
+            };
+            if key == "locations" {
+                if first != b'[' {
+                    panic!("could not deserialize json: locations is not an array");
+                }
+                return true;
+            }
+            match self.deserialize::<IgnoredAny>(first) {
+                Ok(_) => {}
+                Err(e) if e.is_eof() => return false,
+                Err(e) => panic!("could not deserialize json: {e}"),
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for RecordStream<R> {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Record> {
+        if self.position == StreamPosition::Start {
+            self.position = if self.find_locations() {
+                StreamPosition::Locations
+            } else {
+                self.stats.truncated = !self.stats.empty;
+                StreamPosition::End
+            };
+        }
+        while self.position == StreamPosition::Locations {
+            let json_record: JsonRecord = match self.next_token() {
+                Some(b']') => {
+                    self.position = StreamPosition::End;
+                    break;
+                }
+                Some(b',') => continue,
+                Some(first) => match self.deserialize(first) {
+                    Ok(json_record) => json_record,
+                    Err(e) if e.is_eof() => {
+                        self.stats.truncated = true;
+                        self.position = StreamPosition::End;
+                        break;
+                    }
+                    Err(e) => panic!("could not deserialize json: {e}"),
+                },
+                None => {
+                    self.stats.truncated = true;
+                    self.position = StreamPosition::End;
+                    break;
+                }
+            };
+            self.stats.total += 1;
+            match Record::from_json(&json_record) {
+                Ok(record) => return Some(record),
+                Err(RecordError::MissingCoordinates) => self.stats.dropped_no_coords += 1,
+                Err(RecordError::OutOfRangeCoordinates) => self.stats.dropped_out_of_range += 1,
+                Err(RecordError::InvalidTimestamp) => self.stats.dropped_bad_timestamp += 1,
+            }
+        }
+        None
+    }
+}
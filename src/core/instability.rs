@@ -0,0 +1,65 @@
+// this file contains detection of unstable boundary classification between nearby records
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+
+use crate::core::data::{Record, Region};
+
+/// the default distance in meters under which two consecutive records are considered the same spot
+pub const DEFAULT_INSTABILITY_RADIUS_M: f64 = 10.0;
+
+/// a pair of consecutive records that are nearly identical in position but classify to different regions
+#[derive(Debug)]
+pub struct Instability {
+    pub first: Record,
+    pub first_regions: BTreeSet<Region>,
+    pub second: Record,
+    pub second_regions: BTreeSet<Region>,
+}
+
+impl Instability {
+    pub fn distance_m(&self) -> f64 {
+        self.first.distance_km(&self.second) * 1000.0
+    }
+}
+
+impl Display for Instability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |record: &Record, regions: &BTreeSet<Region>| {
+            let names = regions
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            format!(
+                "    | ({}, {}): {}",
+                record.latitude, record.longitude, names
+            )
+        };
+        write!(
+            f,
+            "{}\n    |\n{}\n{}\n    | Distance: {:.1} m\n    |\n",
+            self.first.timestamp.to_rfc2822(),
+            describe(&self.first, &self.first_regions),
+            describe(&self.second, &self.second_regions),
+            self.distance_m()
+        )
+    }
+}
+
+/// finds consecutive records within radius_m meters of each other that classify to different regions
+/// these reveal spots where the boundary data is unreliable
+/// requires records to be sorted by timestamp
+pub fn find_instabilities(records: &[Record], radius_m: f64) -> Vec<Instability> {
+    let regions: Vec<BTreeSet<Region>> = records.iter().map(|r| r.regions()).collect();
+    (1..records.len())
+        .filter(|&i| records[i - 1].distance_km(&records[i]) * 1000.0 <= radius_m)
+        .filter(|&i| regions[i - 1] != regions[i])
+        .map(|i| Instability {
+            first: records[i - 1],
+            first_regions: regions[i - 1].clone(),
+            second: records[i],
+            second_regions: regions[i].clone(),
+        })
+        .collect()
+}
@@ -1,12 +1,14 @@
 // this file contains definitions for the JSON types encountered in the Google Takeout data
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 pub struct JsonDocument {
     pub locations: Vec<JsonRecord>,
 }
 
+// mirrors the schema of the takeout data; not every field is used yet
+#[allow(dead_code)]
 #[derive(Deserialize)]
 pub struct JsonRecord {
     #[serde(rename(deserialize = "latitudeE7"))]
@@ -20,6 +22,7 @@ pub struct JsonRecord {
     pub timestamp: String,
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Deserialize, PartialEq, Eq, Hash, Copy, Clone)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum JsonSource {
@@ -31,4 +34,81 @@ pub enum JsonSource {
     VisitDeparture,
     VisitArrival,
     Manual,
+    /// any source string not listed above, so that a new source in a future export doesn't fail the whole document
+    #[serde(other)]
+    Other,
+}
+
+/// a monthly file of the Semantic Location History, Semantic Location History/<year>/<year>_<MONTH>.json
+#[derive(Deserialize)]
+pub struct JsonSemanticDocument {
+    #[serde(rename(deserialize = "timelineObjects"), default)]
+    pub timeline_objects: Vec<JsonTimelineObject>,
+}
+
+/// an entry of the semantic timeline, which is either a visit to a place or the travel between two places
+#[derive(Deserialize)]
+pub struct JsonTimelineObject {
+    #[serde(rename(deserialize = "placeVisit"))]
+    pub place_visit: Option<JsonPlaceVisit>,
+    #[serde(rename(deserialize = "activitySegment"))]
+    pub activity_segment: Option<JsonActivitySegment>,
+}
+
+#[derive(Deserialize)]
+pub struct JsonPlaceVisit {
+    pub location: JsonPlaceLocation,
+    pub duration: JsonDuration,
+}
+
+// mirrors the schema of the takeout data; not every field is used yet
+#[allow(dead_code)]
+#[derive(Deserialize)]
+pub struct JsonPlaceLocation {
+    #[serde(rename(deserialize = "latitudeE7"))]
+    pub latitude: Option<i64>,
+    #[serde(rename(deserialize = "longitudeE7"))]
+    pub longitude: Option<i64>,
+    #[serde(rename(deserialize = "placeId"))]
+    pub place_id: Option<String>,
+    pub name: Option<String>,
+    pub address: Option<String>,
+}
+
+// mirrors the schema of the takeout data; not every field is used yet
+#[allow(dead_code)]
+#[derive(Deserialize)]
+pub struct JsonActivitySegment {
+    pub duration: JsonDuration,
+    /// the distance traveled in meters
+    pub distance: Option<i64>,
+    #[serde(rename(deserialize = "activityType"))]
+    pub activity_type: Option<String>,
+    /// how sure Google is of the activity type
+    pub confidence: Option<JsonActivityConfidence>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Copy, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum JsonActivityConfidence {
+    Low,
+    Medium,
+    High,
+    /// any confidence string not listed above, such as UNKNOWN_CONFIDENCE
+    #[serde(other)]
+    Other,
+}
+
+/// the start and end of a timeline entry
+/// newer exports have RFC 3339 timestamps, while older ones have epoch milliseconds as strings
+#[derive(Deserialize)]
+pub struct JsonDuration {
+    #[serde(rename(deserialize = "startTimestamp"))]
+    pub start_timestamp: Option<String>,
+    #[serde(rename(deserialize = "startTimestampMs"))]
+    pub start_timestamp_ms: Option<String>,
+    #[serde(rename(deserialize = "endTimestamp"))]
+    pub end_timestamp: Option<String>,
+    #[serde(rename(deserialize = "endTimestampMs"))]
+    pub end_timestamp_ms: Option<String>,
 }
@@ -1,2 +1,23 @@
+pub mod cache;
+pub mod calendar;
+pub mod countries;
+pub mod crossings;
 pub mod data;
+pub mod display;
+pub mod doctor;
+pub mod input;
+pub mod instability;
 pub mod json;
+pub mod names;
+pub mod occupancy;
+pub mod output;
+pub mod residency;
+pub mod schengen;
+pub mod segments;
+pub mod semantic;
+pub mod state;
+pub mod stats;
+pub mod stays;
+pub mod summary;
+pub mod timezones;
+pub mod visits;
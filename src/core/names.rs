@@ -0,0 +1,173 @@
+// this file contains the localized names of regions, for displaying them in languages other than English
+
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use rust_iso3166::CountryCode;
+
+// the language regions are displayed in, chosen once per run
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+/// a language that region names can be displayed in
+/// names that have no translation in the bundled mapping fall back to English
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Language {
+    /// English, as named by the ISO 3166 dataset
+    #[default]
+    En,
+    /// French
+    Fr,
+    /// Spanish
+    Es,
+}
+
+/// sets the language regions are displayed in
+/// must be called before any region is displayed, otherwise English has already been used
+pub fn set_language(language: Language) {
+    if LANGUAGE.set(language).is_err() {
+        panic!("could not display regions in {language:?}: regions were already displayed");
+    }
+}
+
+/// the language regions are displayed in
+pub fn language() -> Language {
+    *LANGUAGE.get_or_init(Language::default)
+}
+
+/// the names of countries as (alpha-2 code, French, Spanish), sorted by code
+/// this covers the most visited countries rather than all of them
+const COUNTRY_NAMES: &[(&str, &str, &str)] = &[
+    ("AE", "Émirats arabes unis", "Emiratos Árabes Unidos"),
+    ("AR", "Argentine", "Argentina"),
+    ("AT", "Autriche", "Austria"),
+    ("AU", "Australie", "Australia"),
+    ("BE", "Belgique", "Bélgica"),
+    ("BG", "Bulgarie", "Bulgaria"),
+    ("BR", "Brésil", "Brasil"),
+    ("CA", "Canada", "Canadá"),
+    ("CH", "Suisse", "Suiza"),
+    ("CL", "Chili", "Chile"),
+    ("CN", "Chine", "China"),
+    ("CO", "Colombie", "Colombia"),
+    ("CR", "Costa Rica", "Costa Rica"),
+    ("CU", "Cuba", "Cuba"),
+    ("CY", "Chypre", "Chipre"),
+    ("CZ", "Tchéquie", "Chequia"),
+    ("DE", "Allemagne", "Alemania"),
+    ("DK", "Danemark", "Dinamarca"),
+    ("DO", "République dominicaine", "República Dominicana"),
+    ("DZ", "Algérie", "Argelia"),
+    ("EC", "Équateur", "Ecuador"),
+    ("EE", "Estonie", "Estonia"),
+    ("EG", "Égypte", "Egipto"),
+    ("ES", "Espagne", "España"),
+    ("FI", "Finlande", "Finlandia"),
+    ("FR", "France", "Francia"),
+    ("GB", "Royaume-Uni", "Reino Unido"),
+    ("GR", "Grèce", "Grecia"),
+    ("HR", "Croatie", "Croacia"),
+    ("HU", "Hongrie", "Hungría"),
+    ("ID", "Indonésie", "Indonesia"),
+    ("IE", "Irlande", "Irlanda"),
+    ("IL", "Israël", "Israel"),
+    ("IN", "Inde", "India"),
+    ("IS", "Islande", "Islandia"),
+    ("IT", "Italie", "Italia"),
+    ("JP", "Japon", "Japón"),
+    ("KR", "Corée du Sud", "Corea del Sur"),
+    ("LT", "Lituanie", "Lituania"),
+    ("LU", "Luxembourg", "Luxemburgo"),
+    ("LV", "Lettonie", "Letonia"),
+    ("MA", "Maroc", "Marruecos"),
+    ("MT", "Malte", "Malta"),
+    ("MX", "Mexique", "México"),
+    ("MY", "Malaisie", "Malasia"),
+    ("NL", "Pays-Bas", "Países Bajos"),
+    ("NO", "Norvège", "Noruega"),
+    ("NZ", "Nouvelle-Zélande", "Nueva Zelanda"),
+    ("PA", "Panama", "Panamá"),
+    ("PE", "Pérou", "Perú"),
+    ("PH", "Philippines", "Filipinas"),
+    ("PL", "Pologne", "Polonia"),
+    ("PT", "Portugal", "Portugal"),
+    ("RO", "Roumanie", "Rumania"),
+    ("RU", "Russie", "Rusia"),
+    ("SE", "Suède", "Suecia"),
+    ("SG", "Singapour", "Singapur"),
+    ("SI", "Slovénie", "Eslovenia"),
+    ("SK", "Slovaquie", "Eslovaquia"),
+    ("TH", "Thaïlande", "Tailandia"),
+    ("TN", "Tunisie", "Túnez"),
+    ("TR", "Turquie", "Turquía"),
+    ("UA", "Ukraine", "Ucrania"),
+    ("US", "États-Unis", "Estados Unidos"),
+    ("UY", "Uruguay", "Uruguay"),
+    ("VN", "Viêt Nam", "Vietnam"),
+    ("ZA", "Afrique du Sud", "Sudáfrica"),
+];
+
+/// the name of a country in the language, if the bundled mapping has one
+pub fn country_name(alpha2: &str, language: Language) -> Option<&'static str> {
+    let (_, fr, es) = COUNTRY_NAMES
+        .binary_search_by_key(&alpha2, |(code, _, _)| code)
+        .ok()
+        .map(|i| COUNTRY_NAMES[i])?;
+    match language {
+        Language::En => None,
+        Language::Fr => Some(fr),
+        Language::Es => Some(es),
+    }
+}
+
+/// the name of a country in the language chosen for the run, falling back to its English name
+pub fn localized_country_name(country: &CountryCode) -> &'static str {
+    country_name(country.alpha2, language()).unwrap_or(country.name)
+}
+
+/// the key a name is sorted by, so that a list of names is alphabetical in any language
+/// letters are compared without case or accents, so that États-Unis is listed among the other names starting with E rather than after Z
+pub fn collation_key(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => "a".to_string(),
+            'æ' => "ae".to_string(),
+            'ç' => "c".to_string(),
+            'è' | 'é' | 'ê' | 'ë' => "e".to_string(),
+            'ì' | 'í' | 'î' | 'ï' => "i".to_string(),
+            'ñ' => "n".to_string(),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => "o".to_string(),
+            'œ' => "oe".to_string(),
+            'ù' | 'ú' | 'û' | 'ü' => "u".to_string(),
+            'ý' | 'ÿ' => "y".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// the name of the missing data marker in the language
+pub fn missing_data_name(language: Language) -> &'static str {
+    match language {
+        Language::En => "Missing Data",
+        Language::Fr => "Données manquantes",
+        Language::Es => "Datos faltantes",
+    }
+}
+
+/// the name of international waters in the language
+pub fn international_waters_name(language: Language) -> &'static str {
+    match language {
+        Language::En => "International Waters",
+        Language::Fr => "Eaux internationales",
+        Language::Es => "Aguas internacionales",
+    }
+}
+
+/// the name of a border zone in the language, which is followed by the countries it is between
+pub fn border_zone_name(language: Language) -> &'static str {
+    match language {
+        Language::En => "Border Zone",
+        Language::Fr => "Zone frontalière",
+        Language::Es => "Zona fronteriza",
+    }
+}
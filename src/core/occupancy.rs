@@ -0,0 +1,158 @@
+// this file contains the day-level occupancy model derived from border crossings
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
+use clap::ValueEnum;
+use rust_iso3166::CountryCode;
+
+use crate::core::data::{with_parent_countries, BorderCrossing, Region, MISSING_DATA_THRESHOLD};
+
+/// the spans between consecutive crossings, as (start, end, regions), skipping missing data
+/// requires crossings to be sorted by timestamp; the span of the final crossing runs until end
+fn spans(
+    crossings: &[BorderCrossing],
+    end: DateTime<Utc>,
+) -> impl Iterator<Item = (DateTime<Utc>, DateTime<Utc>, &BTreeSet<Region>)> {
+    crossings
+        .iter()
+        .enumerate()
+        .filter_map(move |(i, crossing)| {
+            if crossing.new_regions.contains(&Region::MissingData) {
+                return None;
+            }
+            let span_end = match crossings.get(i + 1) {
+                // a missing data crossing is stamped one threshold after the last record, which is when the span really ended
+                Some(next) if next.new_regions.contains(&Region::MissingData) => {
+                    next.timestamp - MISSING_DATA_THRESHOLD
+                }
+                Some(next) => next.timestamp,
+                None => end,
+            };
+            Some((crossing.timestamp, span_end, &crossing.new_regions))
+        })
+}
+
+/// how days are attributed to countries when counting the days spent in each
+#[derive(PartialEq, Eq, Copy, Clone, Default, ValueEnum, Debug)]
+pub enum DayAttribution {
+    /// a day counts for every country occupied at any point during it, so a travel day counts for both sides of a crossing
+    #[default]
+    Any,
+    /// a day counts only for the single country in which the most time was spent during it, with ties broken by alpha-2 code
+    /// time within missing data counts for no country, so a day only partially covered by data is decided by the part that is
+    Majority,
+}
+
+/// the date of the day containing the instant, where each day starts at boundary (UTC) on its date
+fn day_of(instant: DateTime<Utc>, boundary: NaiveTime) -> NaiveDate {
+    (instant - (boundary - NaiveTime::MIN)).date_naive()
+}
+
+/// the days overlapped by a span, as (date, overlap), where each day starts at boundary (UTC) on its date
+fn span_days(
+    span_start: DateTime<Utc>,
+    span_end: DateTime<Utc>,
+    boundary: NaiveTime,
+) -> impl Iterator<Item = (NaiveDate, TimeDelta)> {
+    let last_date = day_of(span_end, boundary);
+    day_of(span_start, boundary)
+        .iter_days()
+        .take_while(move |d| *d <= last_date)
+        .map(move |date| {
+            let day_start = date.and_time(boundary).and_utc();
+            let day_end = day_start + TimeDelta::days(1);
+            (date, span_end.min(day_end) - span_start.max(day_start))
+        })
+}
+
+/// the regions occupied on each calendar date (UTC), derived from the spans between consecutive crossings
+/// a date belongs to every region occupied at any point during it, so travel days count for both sides of a crossing
+/// dates that fall entirely within missing data have no entry
+/// requires crossings to be sorted by timestamp; the span of the final crossing runs until end
+pub fn daily_occupancy(
+    crossings: &[BorderCrossing],
+    end: DateTime<Utc>,
+) -> BTreeMap<NaiveDate, BTreeSet<Region>> {
+    attributed_days(crossings, end, NaiveTime::MIN, DayAttribution::Any)
+}
+
+/// the country in which the most time was spent on each calendar date (UTC), with ties broken by alpha-2 code
+/// subdivisions count towards their parent country; dates that fall entirely within missing data have no entry
+/// requires crossings to be sorted by timestamp; the span of the final crossing runs until end
+pub fn daily_primary_country(
+    crossings: &[BorderCrossing],
+    end: DateTime<Utc>,
+) -> BTreeMap<NaiveDate, CountryCode> {
+    primary_countries(crossings, end, NaiveTime::MIN)
+}
+
+/// the regions each day is attributed to, where each day runs from boundary (UTC) on its date until boundary on the next
+/// with DayAttribution::Any, a day has every region occupied during it; with DayAttribution::Majority, only its primary country
+/// days that fall entirely within missing data have no entry
+/// requires crossings to be sorted by timestamp; the span of the final crossing runs until end
+pub fn attributed_days(
+    crossings: &[BorderCrossing],
+    end: DateTime<Utc>,
+    boundary: NaiveTime,
+    attribution: DayAttribution,
+) -> BTreeMap<NaiveDate, BTreeSet<Region>> {
+    if attribution == DayAttribution::Majority {
+        return primary_countries(crossings, end, boundary)
+            .into_iter()
+            .map(|(date, country)| (date, BTreeSet::from([Region::CountryCode(country)])))
+            .collect();
+    }
+    let mut occupancy: BTreeMap<NaiveDate, BTreeSet<Region>> = BTreeMap::new();
+    for (span_start, span_end, regions) in spans(crossings, end) {
+        for (date, _) in span_days(span_start, span_end, boundary) {
+            occupancy
+                .entry(date)
+                .or_default()
+                .extend(regions.iter().cloned());
+        }
+    }
+    occupancy
+}
+
+/// the country in which the most time was spent on each day, where each day starts at boundary (UTC) on its date
+fn primary_countries(
+    crossings: &[BorderCrossing],
+    end: DateTime<Utc>,
+    boundary: NaiveTime,
+) -> BTreeMap<NaiveDate, CountryCode> {
+    let mut time_spent: BTreeMap<NaiveDate, BTreeMap<&'static str, (CountryCode, TimeDelta)>> =
+        BTreeMap::new();
+    for (span_start, span_end, regions) in spans(crossings, end) {
+        let countries: Vec<CountryCode> = with_parent_countries(regions)
+            .into_iter()
+            .filter_map(|r| match r {
+                Region::CountryCode(c) => Some(c),
+                _ => None,
+            })
+            .collect();
+        for (date, overlap) in span_days(span_start, span_end, boundary) {
+            for country in &countries {
+                time_spent
+                    .entry(date)
+                    .or_default()
+                    .entry(country.alpha2)
+                    .or_insert((*country, TimeDelta::zero()))
+                    .1 += overlap;
+            }
+        }
+    }
+    time_spent
+        .into_iter()
+        .filter_map(|(date, countries)| {
+            // countries are ordered by alpha-2 code, and only a strictly longer time replaces the current choice
+            let mut primary: Option<(CountryCode, TimeDelta)> = None;
+            for (country, time) in countries.into_values() {
+                if primary.is_none_or(|(_, t)| time > t) {
+                    primary = Some((country, time));
+                }
+            }
+            primary.map(|(country, _)| (date, country))
+        })
+        .collect()
+}
@@ -0,0 +1,501 @@
+// this file contains serializations of border crossings and records for use by other tools, and the writer of a command's output
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::ValueEnum;
+use comfy_table::{presets, ContentArrangement, Table};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::core::data::{BorderCrossing, Record, Region, Source, MISSING_DATA_THRESHOLD};
+use crate::core::display::Listing;
+use crate::core::json::JsonActivityConfidence;
+use crate::core::names::localized_country_name;
+use crate::core::semantic::Flight;
+use crate::core::summary::CountrySummary;
+
+/// defines the format of a command's output
+#[derive(PartialEq, Eq, Copy, Clone, ValueEnum, Debug)]
+pub enum OutputFormat {
+    /// human-readable text
+    Text,
+    /// a map bundle that can be loaded by kepler.gl
+    Kepler,
+    /// a JSON array of border crossings
+    Json,
+    /// JSON lines, one border crossing object per line, for consumers that stream the output
+    Jsonl,
+    /// a JSON array of OwnTracks location messages for each record
+    Owntracks,
+    /// a Markdown list of border crossings, for pasting into notes
+    Markdown,
+    /// an aligned table of border crossings, one row per crossing, for scanning long lists
+    Table,
+    /// a GPX track of the records, for importing into mapping tools
+    Gpx,
+}
+
+impl OutputFormat {
+    /// the file extension of output in the format, for naming the files of a batch
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Text | OutputFormat::Table => "txt",
+            OutputFormat::Kepler | OutputFormat::Json | OutputFormat::Owntracks => "json",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Markdown => "md",
+            OutputFormat::Gpx => "gpx",
+        }
+    }
+
+    /// whether the format is a track of the records rather than a list of the crossings
+    pub fn is_track(&self) -> bool {
+        matches!(self, OutputFormat::Owntracks | OutputFormat::Gpx)
+    }
+}
+
+/// the serialized form of a BorderCrossing
+/// Region wraps rust_iso3166 types that are not Serialize, so output gets its own types
+#[derive(Serialize)]
+struct SerializedBorderCrossing {
+    timestamp: String,
+    regions: Vec<SerializedRegion>,
+    /// days until the next crossing; null for the final crossing
+    duration_days: Option<i64>,
+    /// the source of the record that triggered the crossing; "None" for synthetic crossings such as missing data
+    source: Source,
+    /// the flight that corroborates the crossing; omitted unless flights were read and one coincides with it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flight: Option<SerializedFlight>,
+}
+
+/// the serialized form of a Flight
+#[derive(Serialize)]
+struct SerializedFlight {
+    departure: String,
+    arrival: String,
+    /// as in the Semantic Location History, such as "HIGH"; null if the export has none
+    confidence: Option<JsonActivityConfidence>,
+}
+
+impl From<&Flight> for SerializedFlight {
+    fn from(flight: &Flight) -> Self {
+        SerializedFlight {
+            departure: flight.departure.to_rfc3339(),
+            arrival: flight.arrival.to_rfc3339(),
+            confidence: flight.confidence,
+        }
+    }
+}
+
+/// the serialized form of a Region, keeping the code and name separate so consumers don't have to re-geocode
+#[derive(Serialize)]
+struct SerializedRegion {
+    code: Option<String>,
+    name: String,
+}
+
+impl From<&Region> for SerializedRegion {
+    fn from(region: &Region) -> Self {
+        SerializedRegion {
+            code: region.code().map(|c| c.to_owned()),
+            name: region.to_string(),
+        }
+    }
+}
+
+fn serialize_border_crossings(
+    crossings: &[BorderCrossing],
+    listing: &Listing,
+) -> Vec<SerializedBorderCrossing> {
+    listing
+        .list(crossings)
+        .iter()
+        .map(|l| SerializedBorderCrossing {
+            timestamp: l.crossing.timestamp.to_rfc3339(),
+            regions: l
+                .crossing
+                .new_regions
+                .iter()
+                .map(SerializedRegion::from)
+                .collect(),
+            source: l.crossing.source,
+            duration_days: l.duration_days(),
+            flight: l.crossing.flight.as_ref().map(SerializedFlight::from),
+        })
+        .collect()
+}
+
+/// serializes the listed crossings as a JSON array of { timestamp, regions, duration_days } objects
+/// the array is compact by default, since exports can be large; pretty is indented for human inspection
+pub fn border_crossings_to_json(
+    crossings: &[BorderCrossing],
+    listing: &Listing,
+    pretty: bool,
+) -> String {
+    let serialized = serialize_border_crossings(crossings, listing);
+    let result = if pretty {
+        serde_json::to_string_pretty(&serialized)
+    } else {
+        serde_json::to_string(&serialized)
+    };
+    result.unwrap_or_else(|e| panic!("could not serialize border crossings: {e}"))
+}
+
+/// serializes the listed crossings as JSON lines, one compact { timestamp, regions, duration_days } object per line
+/// consumers can process each crossing as it is read, without loading the whole array
+pub fn border_crossings_to_jsonl(crossings: &[BorderCrossing], listing: &Listing) -> String {
+    serialize_border_crossings(crossings, listing)
+        .iter()
+        .map(|c| {
+            serde_json::to_string(c)
+                .unwrap_or_else(|e| panic!("could not serialize border crossing: {e}"))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// renders the listed crossings as Markdown: each crossing's timestamp in bold, its regions as a bulleted list, and its duration in italics
+/// subdivisions are nested under their parent country when it is also one of the crossing's regions
+pub fn border_crossings_to_markdown(crossings: &[BorderCrossing], listing: &Listing) -> String {
+    listing
+        .list(crossings)
+        .iter()
+        .map(|l| {
+            let c = l.crossing;
+            let regions = c
+                .new_regions
+                .iter()
+                .map(|r| match r.parent_country() {
+                    Some(p) if c.new_regions.contains(&Region::CountryCode(p)) => {
+                        format!("    - {r}")
+                    }
+                    _ => format!("- {r}"),
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+            let duration = match l.duration_days() {
+                Some(days) => format!("*Duration: {days} Days*"),
+                None => "*Duration Unknown*".to_string(),
+            };
+            let flight = c
+                .flight
+                .as_ref()
+                .map(|f| format!("*Flight: {f}*\n\n"))
+                .unwrap_or_default();
+            format!(
+                "**{}**\n\n{regions}\n\n{flight}{duration}\n",
+                c.timestamp.to_rfc2822()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// renders the listed crossings as a table with a row of timestamp, regions, and duration for each crossing
+/// regions are joined by commas in their sorted order; the table is never wrapped to the terminal width, so that piped output stays one row per line
+/// if any listed crossing is corroborated by a flight, a column of each crossing's flight is added
+pub fn border_crossings_to_table(crossings: &[BorderCrossing], listing: &Listing) -> String {
+    let listed = listing.list(crossings);
+    let show_flights = listed.iter().any(|l| l.crossing.flight.is_some());
+    let mut header = vec!["Timestamp", "Regions", "Duration"];
+    if show_flights {
+        header.push("Flight");
+    }
+    let mut table = Table::new();
+    table
+        .load_style(presets::ASCII_BORDERS_ONLY_CONDENSED)
+        .set_content_arrangement(ContentArrangement::Disabled)
+        .set_header(header);
+    for l in &listed {
+        let c = l.crossing;
+        let regions = c
+            .new_regions
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        let duration = match l.duration_days() {
+            Some(days) => format!("{days} Days"),
+            None => "Unknown".to_string(),
+        };
+        let mut row = vec![c.timestamp.to_rfc2822(), regions, duration];
+        if show_flights {
+            row.push(c.flight.as_ref().map(|f| f.to_string()).unwrap_or_default());
+        }
+        table.add_row(row);
+    }
+    table.to_string()
+}
+
+/// the format of a summary command's output
+#[derive(PartialEq, Eq, Copy, Clone, ValueEnum, Debug)]
+pub enum SummaryFormat {
+    /// a human-readable table
+    Text,
+    /// a JSON array of summaries
+    Json,
+}
+
+/// the serialized form of a CountrySummary
+#[derive(Serialize)]
+struct SerializedCountrySummary {
+    country: String,
+    code: String,
+    /// whole days, as in the text table
+    total_days: i64,
+    first_seen: String,
+    last_seen: String,
+    visit_count: usize,
+}
+
+/// serializes the per-country summaries as a JSON array, compact unless pretty is set
+pub fn summaries_to_json(summaries: &[CountrySummary], pretty: bool) -> String {
+    let serialized: Vec<SerializedCountrySummary> = summaries
+        .iter()
+        .map(|s| SerializedCountrySummary {
+            country: localized_country_name(&s.country).to_owned(),
+            code: s.country.alpha2.to_owned(),
+            total_days: s.total_time.num_days(),
+            first_seen: s.first_seen.to_rfc3339(),
+            last_seen: s.last_seen.to_rfc3339(),
+            visit_count: s.visit_count,
+        })
+        .collect();
+    let result = if pretty {
+        serde_json::to_string_pretty(&serialized)
+    } else {
+        serde_json::to_string(&serialized)
+    };
+    result.unwrap_or_else(|e| panic!("could not serialize summaries: {e}"))
+}
+
+/// the default number of decimal places coordinates are rounded to in exports, about 10 cm
+pub const DEFAULT_COORD_PRECISION: u32 = 6;
+
+/// rounds a latitude or longitude to the given number of decimal places
+/// coordinates are stored in E7, so anything past 7 places is noise from the division, and fewer places keep exports small
+pub fn round_coordinate(degrees: f64, precision: u32) -> f64 {
+    let scale = 10f64.powi(precision as i32);
+    (degrees * scale).round() / scale
+}
+
+/// the id kepler.gl uses to tie the map config to the crossings dataset
+const KEPLER_DATASET_ID: &str = "border-crossings";
+
+/// serializes the listed crossings as a kepler.gl map bundle: a dataset of crossing points plus a minimal config
+/// crossings without coordinates (such as missing data) are omitted because they cannot be placed on a map
+/// coordinates are rounded to precision decimal places
+pub fn border_crossings_to_kepler(
+    crossings: &[BorderCrossing],
+    listing: &Listing,
+    precision: u32,
+) -> String {
+    let rows: Vec<Value> = listing
+        .list(crossings)
+        .iter()
+        .filter_map(|l| {
+            let c = l.crossing;
+            let (latitude, longitude) = c.coordinates?;
+            let regions = c
+                .new_regions
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            Some(json!([
+                round_coordinate(latitude, precision),
+                round_coordinate(longitude, precision),
+                c.timestamp.to_rfc3339(),
+                regions
+            ]))
+        })
+        .collect();
+
+    let bundle = json!({
+        "datasets": [{
+            "version": "v1",
+            "data": {
+                "id": KEPLER_DATASET_ID,
+                "label": "Border Crossings",
+                "fields": [
+                    { "name": "lat", "type": "real" },
+                    { "name": "lng", "type": "real" },
+                    { "name": "timestamp", "type": "timestamp" },
+                    { "name": "region", "type": "string" },
+                ],
+                "allData": rows,
+            },
+        }],
+        "config": {
+            "version": "v1",
+            "config": {
+                "visState": {
+                    "layers": [{
+                        "id": "border-crossings-points",
+                        "type": "point",
+                        "config": {
+                            "dataId": KEPLER_DATASET_ID,
+                            "label": "Border Crossings",
+                            "columns": { "lat": "lat", "lng": "lng", "altitude": null },
+                            "isVisible": true,
+                        },
+                    }],
+                },
+                "mapState": {},
+                "mapStyle": {},
+            },
+        },
+        "info": {
+            "app": "kepler.gl",
+            "source": env!("CARGO_PKG_NAME"),
+        },
+    });
+
+    serde_json::to_string_pretty(&bundle)
+        .unwrap_or_else(|e| panic!("could not serialize kepler.gl bundle: {e}"))
+}
+
+/// an OwnTracks location message
+#[derive(Serialize)]
+struct OwntracksLocation {
+    #[serde(rename = "_type")]
+    message_type: &'static str,
+    lat: f64,
+    lon: f64,
+    /// the timestamp in epoch seconds
+    tst: i64,
+    /// the accuracy radius in meters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    acc: Option<i64>,
+}
+
+/// serializes the records as a JSON array of OwnTracks location messages, with coordinates rounded to precision decimal places
+pub fn records_to_owntracks(records: &[Record], precision: u32) -> String {
+    let locations: Vec<OwntracksLocation> = records
+        .iter()
+        .map(|r| OwntracksLocation {
+            message_type: "location",
+            lat: round_coordinate(r.latitude, precision),
+            lon: round_coordinate(r.longitude, precision),
+            tst: r.timestamp.timestamp(),
+            acc: r.accuracy,
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&locations)
+        .unwrap_or_else(|e| panic!("could not serialize OwnTracks locations: {e}"))
+}
+
+/// the greatest spacing between the points of an interpolated path across missing data
+const INTERPOLATION_STEP_KM: f64 = 50.0;
+
+/// the GPX type of the track of interpolated paths, by which mapping tools can style it differently
+const INTERPOLATED_TRACK_TYPE: &str = "interpolated";
+
+/// serializes the records as a GPX 1.1 document with a single track, with coordinates rounded to precision decimal places
+/// the track is split into a new segment wherever consecutive records are at least the missing data threshold apart, so that mapping tools don't draw a line across a gap
+/// if interpolate is set, the great-circle path across each gap is added as a segment of a second track of type "interpolated", without timestamps since none of its points were recorded
+/// requires records to be sorted by timestamp
+pub fn records_to_gpx(records: &[Record], precision: u32, interpolate: bool) -> String {
+    let trkpt = |latitude: f64, longitude: f64, time: Option<DateTime<Utc>>| {
+        let attributes = format!(
+            "lat=\"{}\" lon=\"{}\"",
+            round_coordinate(latitude, precision),
+            round_coordinate(longitude, precision),
+        );
+        match time {
+            Some(t) => format!(
+                "      <trkpt {attributes}><time>{}</time></trkpt>",
+                t.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+            ),
+            None => format!("      <trkpt {attributes}/>"),
+        }
+    };
+    let mut segments: Vec<Vec<String>> = vec![];
+    let mut interpolated: Vec<Vec<String>> = vec![];
+    for (i, r) in records.iter().enumerate() {
+        let prev = i.checked_sub(1).and_then(|j| records.get(j));
+        let gap = prev.is_none_or(|prev| r.timestamp - prev.timestamp >= MISSING_DATA_THRESHOLD);
+        if gap {
+            segments.push(vec![]);
+            if let Some(prev) = prev.filter(|_| interpolate) {
+                interpolated.push(
+                    prev.great_circle_path(r, INTERPOLATION_STEP_KM)
+                        .into_iter()
+                        .map(|(latitude, longitude)| trkpt(latitude, longitude, None))
+                        .collect(),
+                );
+            }
+        }
+        segments
+            .last_mut()
+            .unwrap()
+            .push(trkpt(r.latitude, r.longitude, Some(r.timestamp)));
+    }
+    let to_trksegs = |segments: &[Vec<String>]| {
+        segments
+            .iter()
+            .map(|points| format!("    <trkseg>\n{}\n    </trkseg>", points.join("\n")))
+            .collect::<Vec<String>>()
+    };
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+        format!(
+            r#"<gpx version="1.1" creator="{}" xmlns="http://www.topografix.com/GPX/1/1">"#,
+            env!("CARGO_PKG_NAME")
+        ),
+        "  <trk>".to_string(),
+        "    <name>Location History</name>".to_string(),
+    ];
+    lines.extend(to_trksegs(&segments));
+    lines.push("  </trk>".to_string());
+    // the probable paths are a separate track with untimed points, so viewers can style them apart from the recorded track
+    if !interpolated.is_empty() {
+        lines.extend([
+            "  <trk>".to_string(),
+            "    <name>Probable Path During Missing Data</name>".to_string(),
+            format!("    <type>{INTERPOLATED_TRACK_TYPE}</type>"),
+        ]);
+        lines.extend(to_trksegs(&interpolated));
+        lines.push("  </trk>".to_string());
+    }
+    lines.push("</gpx>".to_string());
+    lines.join("\n")
+}
+
+/// writes the command's output to the given file, or to stdout if no file is given
+/// the file receives exactly what would have been printed to stdout, including the trailing newline
+/// if compress is set, the file is gzipped and the .gz extension is appended when missing
+pub fn write_output(contents: &str, output: &Option<PathBuf>, compress: bool) {
+    let Some(path) = output else {
+        println!("{contents}");
+        return;
+    };
+    let contents = format!("{contents}\n");
+
+    if compress {
+        let path = if path.extension() == Some(OsStr::new("gz")) {
+            path.clone()
+        } else {
+            let mut name = path.clone().into_os_string();
+            name.push(".gz");
+            PathBuf::from(name)
+        };
+        let file =
+            fs::File::create(&path).unwrap_or_else(|e| panic!("could not create {path:?}: {e}"));
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        encoder
+            .write_all(contents.as_bytes())
+            .and_then(|_| encoder.finish().map(|_| ()))
+            .unwrap_or_else(|e| panic!("could not write compressed output to {path:?}: {e}"));
+    } else {
+        fs::write(path, contents)
+            .unwrap_or_else(|e| panic!("could not write output to {path:?}: {e}"));
+    }
+}
@@ -0,0 +1,89 @@
+// this file contains the per-year residency table (days present in each country per year)
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Display};
+
+use chrono::{Datelike, NaiveDate};
+use rust_iso3166::CountryCode;
+
+use crate::core::data::{with_parent_countries, Region};
+
+/// the number of days present in each country in each year
+/// a day counts towards every country occupied at any point during its calendar date, so travel days count for both countries
+#[derive(Debug)]
+pub struct ResidencyTable {
+    pub years: Vec<i32>,
+    /// the countries present in the table, sorted by alpha-2 code
+    pub countries: Vec<CountryCode>,
+    days: HashMap<(i32, &'static str), usize>,
+}
+
+impl ResidencyTable {
+    pub fn new(occupancy: &BTreeMap<NaiveDate, BTreeSet<Region>>) -> Self {
+        let mut years: BTreeSet<i32> = BTreeSet::new();
+        let mut countries: BTreeMap<&'static str, CountryCode> = BTreeMap::new();
+        let mut days: HashMap<(i32, &'static str), usize> = HashMap::new();
+        for (date, regions) in occupancy {
+            for region in with_parent_countries(regions) {
+                if let Region::CountryCode(c) = region {
+                    years.insert(date.year());
+                    countries.insert(c.alpha2, c);
+                    *days.entry((date.year(), c.alpha2)).or_default() += 1;
+                }
+            }
+        }
+        ResidencyTable {
+            years: years.into_iter().collect(),
+            countries: countries.into_values().collect(),
+            days,
+        }
+    }
+
+    /// the number of days present in the country in the year
+    pub fn days(&self, year: i32, country: &CountryCode) -> usize {
+        self.days.get(&(year, country.alpha2)).copied().unwrap_or(0)
+    }
+
+    /// renders the table as CSV, with a header row of alpha-2 codes
+    pub fn to_csv(&self) -> String {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        let header = std::iter::once("Year").chain(self.countries.iter().map(|c| c.alpha2));
+        writer
+            .write_record(header)
+            .unwrap_or_else(|e| panic!("could not write csv header: {e}"));
+        for year in &self.years {
+            let row = std::iter::once(year.to_string()).chain(
+                self.countries
+                    .iter()
+                    .map(|c| self.days(*year, c).to_string()),
+            );
+            writer
+                .write_record(row)
+                .unwrap_or_else(|e| panic!("could not write csv row: {e}"));
+        }
+        let bytes = writer
+            .into_inner()
+            .unwrap_or_else(|e| panic!("could not write csv: {e}"));
+        String::from_utf8(bytes).unwrap_or_else(|e| panic!("could not read csv as utf-8: {e}"))
+    }
+}
+
+impl Display for ResidencyTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = self
+            .countries
+            .iter()
+            .map(|c| format!("{:>6}", c.alpha2))
+            .collect::<String>();
+        let mut lines = vec![format!("{:<6}{header}", "Year")];
+        for year in &self.years {
+            let row = self
+                .countries
+                .iter()
+                .map(|c| format!("{:>6}", self.days(*year, c)))
+                .collect::<String>();
+            lines.push(format!("{year:<6}{row}"));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
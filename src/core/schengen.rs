@@ -0,0 +1,81 @@
+// this file contains the Schengen area 90/180 day rule calculation
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+
+use chrono::{NaiveDate, TimeDelta};
+
+use crate::core::data::{with_parent_countries, Region};
+
+/// ISO 3166-1 alpha-2 codes of the countries in the Schengen area
+pub const SCHENGEN_COUNTRIES: [&str; 29] = [
+    "AT", "BE", "BG", "CH", "CZ", "DE", "DK", "EE", "ES", "FI", "FR", "GR", "HR", "HU", "IS", "IT",
+    "LI", "LT", "LU", "LV", "MT", "NL", "NO", "PL", "PT", "RO", "SE", "SI", "SK",
+];
+
+/// the length of the trailing window in which days are counted
+pub const SCHENGEN_WINDOW_DAYS: i64 = 180;
+
+/// the maximum number of days that may be spent in the Schengen area within the window
+pub const SCHENGEN_MAX_DAYS: usize = 90;
+
+/// a day spent in the Schengen area and the number of Schengen days used in the trailing window ending on it
+#[derive(Debug)]
+pub struct SchengenDay {
+    pub date: NaiveDate,
+    pub days_used: usize,
+}
+
+impl SchengenDay {
+    pub fn exceeds_limit(&self) -> bool {
+        self.days_used > SCHENGEN_MAX_DAYS
+    }
+}
+
+impl Display for SchengenDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = if self.exceeds_limit() {
+            "    | EXCEEDS LIMIT"
+        } else {
+            ""
+        };
+        write!(
+            f,
+            "{}    | {:>3} / {SCHENGEN_MAX_DAYS} days{flag}",
+            self.date, self.days_used
+        )
+    }
+}
+
+fn is_schengen(region: &Region) -> bool {
+    match region {
+        Region::CountryCode(c) => SCHENGEN_COUNTRIES.contains(&c.alpha2),
+        _ => false,
+    }
+}
+
+/// for each day spent in the Schengen area, counts the Schengen days in the trailing window ending on that day
+pub fn schengen_report(occupancy: &BTreeMap<NaiveDate, BTreeSet<Region>>) -> Vec<SchengenDay> {
+    let schengen_dates: Vec<NaiveDate> = occupancy
+        .iter()
+        .filter(|(_, regions)| with_parent_countries(regions).iter().any(is_schengen))
+        .map(|(date, _)| *date)
+        .collect();
+
+    // the dates are sorted, so the trailing window can be tracked with a lagging index
+    let mut window_start = 0;
+    schengen_dates
+        .iter()
+        .enumerate()
+        .map(|(i, date)| {
+            let earliest = *date - TimeDelta::days(SCHENGEN_WINDOW_DAYS - 1);
+            while schengen_dates[window_start] < earliest {
+                window_start += 1;
+            }
+            SchengenDay {
+                date: *date,
+                days_used: i - window_start + 1,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,58 @@
+// this file contains the conversion of border crossings into continuous stays
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::core::data::{BorderCrossing, Region};
+
+/// a continuous stay in a set of regions, from one crossing to the next
+#[derive(Debug)]
+pub struct Segment {
+    pub start: DateTime<Utc>,
+    /// None for the final segment, which is open-ended
+    pub end: Option<DateTime<Utc>>,
+    pub regions: BTreeSet<Region>,
+}
+
+impl Segment {
+    pub fn duration(&self) -> Option<TimeDelta> {
+        self.end.map(|end| end - self.start)
+    }
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let regions = self
+            .regions
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        let start = self.start.format("%Y-%m-%d");
+        match (self.end, self.duration()) {
+            (Some(end), Some(duration)) => write!(
+                f,
+                "{start} → {} ({}d): {regions}",
+                end.format("%Y-%m-%d"),
+                duration.num_days()
+            ),
+            _ => write!(f, "{start} → present: {regions}"),
+        }
+    }
+}
+
+/// converts the crossings into the segments between them, each ending where the next crossing begins
+/// requires crossings to be sorted by timestamp
+pub fn segments(crossings: &[BorderCrossing]) -> Vec<Segment> {
+    crossings
+        .iter()
+        .enumerate()
+        .map(|(i, crossing)| Segment {
+            start: crossing.timestamp,
+            end: crossings.get(i + 1).map(|next| next.timestamp),
+            regions: crossing.new_regions.clone(),
+        })
+        .collect()
+}
@@ -0,0 +1,314 @@
+// this file contains the reading of place visits from the Semantic Location History, which Takeout exports alongside Records.json
+
+use std::ffi::OsStr;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use flate2::read::GzDecoder;
+use log::warn;
+use zip::ZipArchive;
+
+use crate::core::data::BorderCrossing;
+use crate::core::input::is_tar_gz;
+use crate::core::json::{
+    JsonActivityConfidence, JsonDuration, JsonPlaceVisit, JsonSemanticDocument,
+};
+
+/// a visit to a place, as recorded in the Semantic Location History
+#[derive(Debug)]
+pub struct PlaceVisit {
+    /// the name of the place; None for places Google couldn't name
+    pub name: Option<String>,
+    pub address: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl PlaceVisit {
+    pub fn duration(&self) -> TimeDelta {
+        self.end - self.start
+    }
+
+    /// converts a visit from the takeout data
+    /// returns None if the visit's start or end can't be parsed
+    fn from_json(visit: &JsonPlaceVisit) -> Option<Self> {
+        let (start, end) = parse_duration(&visit.duration)?;
+        Some(PlaceVisit {
+            name: visit.location.name.clone(),
+            address: visit.location.address.clone(),
+            latitude: visit.location.latitude.map(|l| l as f64 / 1e7),
+            longitude: visit.location.longitude.map(|l| l as f64 / 1e7),
+            start,
+            end,
+        })
+    }
+}
+
+impl Display for PlaceVisit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match (&self.name, self.latitude, self.longitude) {
+            (Some(name), _, _) => name.clone(),
+            (None, Some(latitude), Some(longitude)) => {
+                format!("Unnamed Place ({latitude:.5}, {longitude:.5})")
+            }
+            _ => "Unnamed Place".to_string(),
+        };
+        let duration = self.duration();
+        writeln!(f, "{}\n    |\n    | {name}", self.start.to_rfc2822())?;
+        if let Some(address) = &self.address {
+            writeln!(f, "    | {}", address.replace('\n', ", "))?;
+        }
+        write!(
+            f,
+            "    | Departed: {}\n    | Duration: {}h {}m\n    |\n",
+            self.end.to_rfc2822(),
+            duration.num_hours(),
+            duration.num_minutes() % 60
+        )
+    }
+}
+
+/// parses the start and end of a timeline entry, from whichever of the timestamp formats it has
+fn parse_duration(duration: &JsonDuration) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let parse =
+        |timestamp: &Option<String>, timestamp_ms: &Option<String>| match (timestamp, timestamp_ms)
+        {
+            (Some(t), _) => t.parse::<DateTime<Utc>>().ok(),
+            (None, Some(ms)) => ms
+                .parse::<i64>()
+                .ok()
+                .and_then(DateTime::from_timestamp_millis),
+            (None, None) => None,
+        };
+    Some((
+        parse(&duration.start_timestamp, &duration.start_timestamp_ms)?,
+        parse(&duration.end_timestamp, &duration.end_timestamp_ms)?,
+    ))
+}
+
+/// whether a file name is that of a monthly Semantic Location History file, such as 2023_MAY.json
+/// the folders within the export are localized, but the monthly file names are not, so files are matched by name alone
+fn is_semantic_month_file(name: &str) -> bool {
+    let Some(stem) = name.strip_suffix(".json") else {
+        return false;
+    };
+    match stem.split_once('_') {
+        Some((year, month)) => {
+            year.len() == 4
+                && year.chars().all(|c| c.is_ascii_digit())
+                && !month.is_empty()
+                && month.chars().all(|c| c.is_ascii_uppercase())
+        }
+        None => false,
+    }
+}
+
+/// whether the path within a directory or archive is a monthly Semantic Location History file
+fn is_semantic_month_path(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(is_semantic_month_file)
+}
+
+/// the monthly files within a directory and the folders below it, sorted by path
+fn find_month_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = vec![];
+    for path in fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("could not read directory {dir:?}: {e}"))
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+    {
+        if path.is_dir() {
+            files.extend(find_month_files(&path));
+        } else if is_semantic_month_path(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+/// reads an entry of an archive or a file as a utf-8 string
+fn read_to_string(mut reader: impl Read, name: &str) -> String {
+    let mut buf = String::new();
+    reader
+        .read_to_string(&mut buf)
+        .unwrap_or_else(|e| panic!("could not read {name} as utf-8 string: {e}"));
+    buf
+}
+
+/// the documents of the monthly files in the path, which may be a monthly .json file, a directory containing them, or a .zip or .tgz export
+fn read_month_documents(path: &Path) -> Vec<(String, String)> {
+    if path.is_dir() {
+        find_month_files(path)
+            .iter()
+            .map(|p| {
+                let document = fs::read_to_string(p)
+                    .unwrap_or_else(|e| panic!("could not read file {p:?}: {e}"));
+                (p.to_string_lossy().into_owned(), document)
+            })
+            .collect()
+    } else if path.extension() == Some(OsStr::new("zip")) {
+        let file = fs::File::open(path).unwrap_or_else(|e| panic!("could not open {path:?}: {e}"));
+        let mut archive = ZipArchive::new(std::io::BufReader::new(file))
+            .unwrap_or_else(|e| panic!("could not read archive {path:?}: {e}"));
+        let mut names: Vec<String> = archive
+            .file_names()
+            .filter(|name| is_semantic_month_path(Path::new(name)))
+            .map(|name| name.to_owned())
+            .collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| {
+                let entry = archive
+                    .by_name(&name)
+                    .unwrap_or_else(|e| panic!("could not extract {name}: {e}"));
+                let document = read_to_string(entry, &name);
+                (name, document)
+            })
+            .collect()
+    } else if is_tar_gz(path) {
+        let file = fs::File::open(path).unwrap_or_else(|e| panic!("could not open {path:?}: {e}"));
+        let mut archive = tar::Archive::new(GzDecoder::new(std::io::BufReader::new(file)));
+        let mut documents: Vec<(String, String)> = archive
+            .entries()
+            .unwrap_or_else(|e| panic!("could not read archive {path:?}: {e}"))
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.path().ok()?.to_string_lossy().into_owned();
+                is_semantic_month_path(Path::new(&name))
+                    .then(|| (name.clone(), read_to_string(entry, &name)))
+            })
+            .collect();
+        documents.sort();
+        documents
+    } else if path.extension() == Some(OsStr::new("json")) {
+        let document = fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("could not read file {path:?}: {e}"));
+        vec![(path.to_string_lossy().into_owned(), document)]
+    } else {
+        let ext = path.extension().unwrap_or_default().to_string_lossy();
+        panic!("could not handle unknown filetype, must be a directory or one of {{.zip, .tgz, .tar.gz, .json}}: {ext}");
+    }
+}
+
+/// parses the monthly files in each of the given paths
+/// panics if a path contains no monthly files
+fn read_semantic_documents(paths: &[PathBuf]) -> Vec<JsonSemanticDocument> {
+    let mut documents: Vec<JsonSemanticDocument> = vec![];
+    for path in paths {
+        let month_documents = read_month_documents(path);
+        if month_documents.is_empty() {
+            panic!("could not find any Semantic Location History files in {path:?}");
+        }
+        documents.extend(month_documents.iter().map(|(name, document)| {
+            serde_json::from_str(document).unwrap_or_else(|e| panic!("could not parse {name}: {e}"))
+        }));
+    }
+    documents
+}
+
+/// reads the place visits from each of the given paths, sorted by start
+/// activity segments, the travel between places, are not visits and are skipped
+/// visits whose start or end can't be parsed are dropped, and counted in a warning
+/// panics if a path contains no monthly files
+pub fn read_place_visits(paths: &[PathBuf]) -> Vec<PlaceVisit> {
+    let mut visits: Vec<PlaceVisit> = vec![];
+    let mut dropped = 0;
+    for document in read_semantic_documents(paths) {
+        for visit in document
+            .timeline_objects
+            .iter()
+            .filter_map(|o| o.place_visit.as_ref())
+        {
+            match PlaceVisit::from_json(visit) {
+                Some(v) => visits.push(v),
+                None => dropped += 1,
+            }
+        }
+    }
+    if dropped > 0 {
+        warn!("dropped {dropped} place visits without a valid start and end");
+    }
+    visits.sort_by_key(|v| v.start);
+    visits
+}
+
+/// how long after a flight lands a crossing still coincides with it
+/// phones are often off in the air and slow to get a fix after landing, so the first record in the new country can trail the landing
+pub const FLIGHT_ARRIVAL_TOLERANCE: TimeDelta = TimeDelta::hours(6);
+
+/// an activity segment that Google classified as flying
+#[derive(Debug, Clone)]
+pub struct Flight {
+    pub departure: DateTime<Utc>,
+    pub arrival: DateTime<Utc>,
+    pub confidence: Option<JsonActivityConfidence>,
+}
+
+impl Display for Flight {
+    /// the landing, and how sure Google is that it was a flight when the export says
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "landed {}", self.arrival.to_rfc2822())?;
+        match self.confidence {
+            Some(JsonActivityConfidence::High) => write!(f, " (high confidence)"),
+            Some(JsonActivityConfidence::Medium) => write!(f, " (medium confidence)"),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Flight {
+    /// whether a crossing at the timestamp coincides with the flight: during it, or within FLIGHT_ARRIVAL_TOLERANCE of its landing
+    pub fn coincides_with(&self, timestamp: DateTime<Utc>) -> bool {
+        self.departure <= timestamp && timestamp <= self.arrival + FLIGHT_ARRIVAL_TOLERANCE
+    }
+}
+
+/// reads the flights from the activity segments in each of the given paths, sorted by departure
+/// segments classified as flying with low confidence are skipped, since they are as often a fast train or a gap in the data
+/// panics if a path contains no monthly files
+pub fn read_flights(paths: &[PathBuf]) -> Vec<Flight> {
+    let mut flights: Vec<Flight> = read_semantic_documents(paths)
+        .iter()
+        .flat_map(|d| &d.timeline_objects)
+        .filter_map(|o| o.activity_segment.as_ref())
+        .filter(|a| a.activity_type.as_deref() == Some("FLYING"))
+        .filter(|a| a.confidence != Some(JsonActivityConfidence::Low))
+        .filter_map(|a| {
+            let (departure, arrival) = parse_duration(&a.duration)?;
+            Some(Flight {
+                departure,
+                arrival,
+                confidence: a.confidence,
+            })
+        })
+        .collect();
+    flights.sort_by_key(|f| f.departure);
+    flights
+}
+
+/// the flight that a crossing at the timestamp coincides with, if any
+/// requires flights to be sorted by departure, as read_flights returns them
+pub fn flight_at(flights: &[Flight], timestamp: DateTime<Utc>) -> Option<&Flight> {
+    let i = flights.partition_point(|f| f.departure <= timestamp);
+    flights[..i]
+        .iter()
+        .rev()
+        .find(|f| f.coincides_with(timestamp))
+}
+
+/// sets the flight of each crossing that coincides with one, returning how many do
+/// a flight corroborates a crossing made by a record, but not missing data
+/// requires flights to be sorted by departure, as read_flights returns them
+pub fn corroborate_crossings(crossings: &mut [BorderCrossing], flights: &[Flight]) -> usize {
+    for crossing in crossings.iter_mut().filter(|c| c.coordinates.is_some()) {
+        crossing.flight = flight_at(flights, crossing.timestamp).cloned();
+    }
+    crossings.iter().filter(|c| c.flight.is_some()).count()
+}
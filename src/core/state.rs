@@ -0,0 +1,66 @@
+// this file contains the state kept between runs, so that a recurring run only reports what is new since the last one
+
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::core::data::Record;
+
+/// the version of the state file format, bumped whenever the representation changes
+pub const STATE_VERSION: u32 = 1;
+
+/// the serialized form of a state file
+#[derive(Serialize, Deserialize)]
+struct StateDocument {
+    version: u32,
+    /// rfc 3339; the timestamp of the latest record processed by the last run
+    last_timestamp: String,
+}
+
+/// the timestamp of the latest record processed by the last run, as stored in the state file
+/// returns None if the file doesn't exist yet, so that the first run processes everything
+/// panics if the file exists but can't be read, rather than silently reprocessing everything
+pub fn read_last_timestamp(path: &Path) -> Option<DateTime<Utc>> {
+    if !path.exists() {
+        info!("no state file at {path:?}, processing every record");
+        return None;
+    }
+    let json_str = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("could not read state file {path:?}: {e}"));
+    let document: StateDocument = serde_json::from_str(&json_str)
+        .unwrap_or_else(|e| panic!("could not parse state file {path:?}: {e}"));
+    if document.version != STATE_VERSION {
+        panic!(
+            "could not read state file {path:?} of version {}, expected version {STATE_VERSION}",
+            document.version
+        );
+    }
+    let last_timestamp = document
+        .last_timestamp
+        .parse::<DateTime<Utc>>()
+        .unwrap_or_else(|e| panic!("could not parse timestamp in state file {path:?}: {e}"));
+    Some(last_timestamp)
+}
+
+/// stores the timestamp of the latest record processed in the state file, replacing what was there
+pub fn write_last_timestamp(path: &Path, last_timestamp: DateTime<Utc>) {
+    let document = StateDocument {
+        version: STATE_VERSION,
+        last_timestamp: last_timestamp.to_rfc3339(),
+    };
+    let json_str = serde_json::to_string_pretty(&document)
+        .unwrap_or_else(|e| panic!("could not serialize state: {e}"));
+    fs::write(path, json_str)
+        .unwrap_or_else(|e| panic!("could not write state file {path:?}: {e}"));
+}
+
+/// the records after the cutoff, preceded by the last record at or before it
+/// the earlier record gives detection the regions the new records are compared against, so that staying put isn't reported as a first crossing
+/// requires records to be sorted by timestamp
+pub fn records_since(records: &[Record], cutoff: DateTime<Utc>) -> Vec<Record> {
+    let first_new = records.partition_point(|r| r.timestamp <= cutoff);
+    records[first_new.saturating_sub(1)..].to_vec()
+}
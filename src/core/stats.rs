@@ -0,0 +1,90 @@
+// this file contains the summary statistics reported by the stats command
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+
+use chrono::{DateTime, Utc};
+
+use crate::core::crossings::collapse_consecutive_duplicates;
+use crate::core::data::{
+    with_parent_countries, BorderCrossing, Record, Region, MISSING_DATA_THRESHOLD,
+};
+
+/// summary statistics over a location history
+#[derive(Debug)]
+pub struct Stats {
+    /// sum of the great-circle distances between consecutive records, excluding missing data gaps
+    pub distance_km: f64,
+    /// number of countries with a record in them, counting a subdivision towards its parent country
+    pub countries: usize,
+    /// number of crossings into a new set of regions, not counting missing data markers, the first record, or the return to the same regions after a gap
+    pub crossings: usize,
+    pub first_timestamp: Option<DateTime<Utc>>,
+    pub last_timestamp: Option<DateTime<Utc>>,
+}
+
+impl Stats {
+    /// requires records to be sorted by timestamp
+    pub fn new(records: &[Record], crossings: &[BorderCrossing]) -> Self {
+        // consecutive records separated by a missing data gap are skipped
+        // otherwise a long absence would draw a straight line across the planet and inflate the total
+        let distance_km = records
+            .windows(2)
+            .filter(|w| w[1].timestamp - w[0].timestamp < MISSING_DATA_THRESHOLD)
+            .map(|w| w[0].distance_km(&w[1]))
+            .sum();
+
+        let countries = records
+            .iter()
+            .flat_map(|r| with_parent_countries(&r.regions()))
+            .filter(|r| matches!(r, Region::CountryCode(_)))
+            .collect::<BTreeSet<Region>>()
+            .len();
+
+        // with the missing data markers stripped, the crossing after a gap into the same regions is a duplicate of the one before the gap
+        let crossings: Vec<BorderCrossing> = crossings
+            .iter()
+            .filter(|c| !c.new_regions.contains(&Region::MissingData))
+            .cloned()
+            .collect();
+        // the first crossing is where the history starts, rather than a move across a border
+        let crossings = collapse_consecutive_duplicates(&crossings)
+            .len()
+            .saturating_sub(1);
+
+        Stats {
+            distance_km,
+            countries,
+            crossings,
+            first_timestamp: records.first().map(|r| r.timestamp),
+            last_timestamp: records.last().map(|r| r.timestamp),
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let timestamp_str = |t: Option<DateTime<Utc>>| match t {
+            Some(t) => t.to_rfc2822(),
+            None => "Unknown".to_string(),
+        };
+        let days_covered = match (self.first_timestamp, self.last_timestamp) {
+            (Some(first), Some(last)) => (last - first).num_days().to_string(),
+            _ => "Unknown".to_string(),
+        };
+        let rows = [
+            ("Distance Traveled", format!("{:.1} km", self.distance_km)),
+            ("Countries Visited", self.countries.to_string()),
+            ("Border Crossings", self.crossings.to_string()),
+            ("First Record", timestamp_str(self.first_timestamp)),
+            ("Last Record", timestamp_str(self.last_timestamp)),
+            ("Days Covered", days_covered),
+        ];
+        let table = rows
+            .iter()
+            .map(|(label, value)| format!("{label:<20}{value}"))
+            .collect::<Vec<String>>()
+            .join("\n");
+        write!(f, "{table}")
+    }
+}
@@ -0,0 +1,173 @@
+// this file contains the clustering of consecutive nearby records into stay points
+
+use std::fmt::{self, Display};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::core::data::{BorderCrossing, Record, MISSING_DATA_THRESHOLD};
+use crate::core::output::round_coordinate;
+
+/// the default distance in meters from a stay's centroid within which a record is part of the stay
+pub const DEFAULT_STAY_RADIUS_M: f64 = 200.0;
+
+/// the number of decimal places the coordinates of a redacted stay are rounded to, about 1 km
+pub const REDACTED_PRECISION: u32 = 2;
+
+/// a place where a while was spent, clustered from consecutive nearby records
+#[derive(Debug)]
+pub struct Stay {
+    /// the mean latitude of the stay's records
+    pub latitude: f64,
+    /// the mean longitude of the stay's records
+    pub longitude: f64,
+    pub arrival: DateTime<Utc>,
+    pub departure: DateTime<Utc>,
+    /// the number of records clustered into the stay
+    pub records: usize,
+}
+
+impl Stay {
+    pub fn dwell(&self) -> TimeDelta {
+        self.departure - self.arrival
+    }
+
+    /// the coordinates that stand in for every record of the stay when it is redacted: its centroid, rounded to REDACTED_PRECISION decimal places
+    pub fn redacted_coordinates(&self) -> (f64, f64) {
+        (
+            round_coordinate(self.latitude, REDACTED_PRECISION),
+            round_coordinate(self.longitude, REDACTED_PRECISION),
+        )
+    }
+
+    fn contains(&self, timestamp: DateTime<Utc>) -> bool {
+        self.arrival <= timestamp && timestamp <= self.departure
+    }
+}
+
+impl Display for Stay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dwell = self.dwell();
+        write!(
+            f,
+            "{}\n    |\n    | ({:.5}, {:.5})\n    | Departed: {}\n    | Dwell: {}h {}m ({} records)\n    |\n",
+            self.arrival.to_rfc2822(),
+            self.latitude,
+            self.longitude,
+            self.departure.to_rfc2822(),
+            dwell.num_hours(),
+            dwell.num_minutes() % 60,
+            self.records
+        )
+    }
+}
+
+/// consecutive nearby records being clustered into a stay
+/// the sums of the coordinates are kept as records are added, so the centroid doesn't have to be recomputed from every record
+struct Cluster {
+    first: Record,
+    last: Record,
+    latitude_sum: f64,
+    longitude_sum: f64,
+    records: usize,
+}
+
+impl Cluster {
+    fn new(record: &Record) -> Self {
+        Cluster {
+            first: *record,
+            last: *record,
+            latitude_sum: record.latitude,
+            longitude_sum: record.longitude,
+            records: 1,
+        }
+    }
+
+    fn push(&mut self, record: &Record) {
+        self.last = *record;
+        self.latitude_sum += record.latitude;
+        self.longitude_sum += record.longitude;
+        self.records += 1;
+    }
+
+    /// a record at the mean position of the cluster, for measuring distances from it
+    fn centroid(&self) -> Record {
+        let n = self.records as f64;
+        Record {
+            latitude: self.latitude_sum / n,
+            longitude: self.longitude_sum / n,
+            ..self.first
+        }
+    }
+
+    fn to_stay(&self, min_dwell: TimeDelta) -> Option<Stay> {
+        let centroid = self.centroid();
+        let stay = Stay {
+            latitude: centroid.latitude,
+            longitude: centroid.longitude,
+            arrival: self.first.timestamp,
+            departure: self.last.timestamp,
+            records: self.records,
+        };
+        (stay.dwell() >= min_dwell).then_some(stay)
+    }
+}
+
+/// clusters consecutive records into stays
+/// a record joins the current cluster if it is within radius_m meters of the cluster's centroid, otherwise it starts a new cluster
+/// a gap of at least the missing data threshold also starts a new cluster, since nothing is known about the time in between
+/// clusters spanning at least min_dwell are stays; shorter ones are movement and are discarded
+/// requires records to be sorted by timestamp
+pub fn find_stays(records: &[Record], radius_m: f64, min_dwell: TimeDelta) -> Vec<Stay> {
+    let mut stays: Vec<Stay> = vec![];
+    let mut cluster: Option<Cluster> = None;
+    for record in records {
+        match cluster.as_mut() {
+            Some(c) => {
+                let moved = c.centroid().distance_km(record) * 1000.0 > radius_m;
+                let gap = record.timestamp - c.last.timestamp >= MISSING_DATA_THRESHOLD;
+                if moved || gap {
+                    stays.extend(c.to_stay(min_dwell));
+                    *c = Cluster::new(record);
+                } else {
+                    c.push(record);
+                }
+            }
+            None => cluster = Some(Cluster::new(record)),
+        }
+    }
+    stays.extend(cluster.and_then(|c| c.to_stay(min_dwell)));
+    stays
+}
+
+/// the stay that a timestamp falls within, if any
+/// requires stays to be sorted by arrival, as find_stays returns them
+fn stay_at(stays: &[Stay], timestamp: DateTime<Utc>) -> Option<&Stay> {
+    let i = stays.partition_point(|s| s.arrival <= timestamp);
+    i.checked_sub(1)
+        .and_then(|j| stays.get(j))
+        .filter(|s| s.contains(timestamp))
+}
+
+/// blurs the places where at least min_dwell was spent, such as a home or workplace, so that a shared export doesn't reveal them
+/// every record within such a stay is moved to the stay's redacted coordinates, so the stay shows up as one coarse point
+/// returns the redacted stays, for redacting the crossings detected from the records with redact_crossings
+/// records should be redacted after crossings are detected from them, since a coarse point can fall in a different region
+/// requires records to be sorted by timestamp
+pub fn redact_long_stays(records: &mut [Record], radius_m: f64, min_dwell: TimeDelta) -> Vec<Stay> {
+    let stays = find_stays(records, radius_m, min_dwell);
+    for record in records.iter_mut() {
+        if let Some(stay) = stay_at(&stays, record.timestamp) {
+            (record.latitude, record.longitude) = stay.redacted_coordinates();
+        }
+    }
+    stays
+}
+
+/// moves the coordinates of each crossing made during one of the stays to the stay's redacted coordinates
+pub fn redact_crossings(crossings: &mut [BorderCrossing], stays: &[Stay]) {
+    for crossing in crossings.iter_mut().filter(|c| c.coordinates.is_some()) {
+        if let Some(stay) = stay_at(stays, crossing.timestamp) {
+            crossing.coordinates = Some(stay.redacted_coordinates());
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// this file contains the aggregation of visits into the cumulative time spent in each country
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeDelta, Utc};
+use clap::ValueEnum;
+use rust_iso3166::CountryCode;
+
+use crate::core::data::{with_parent_countries, BorderCrossing, Region};
+use crate::core::names::localized_country_name;
+use crate::core::visits::Visit;
+
+/// the cumulative time spent in a country across all visits to it
+#[derive(Debug)]
+pub struct CountrySummary {
+    pub country: CountryCode,
+    pub total_time: TimeDelta,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub visit_count: usize,
+}
+
+impl Display for CountrySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let visits = match self.visit_count {
+            1 => "1 visit".to_string(),
+            n => format!("{n} visits"),
+        };
+        write!(
+            f,
+            "{:<40}{:>8} Days    {visits}",
+            format!(
+                "{} ({})",
+                localized_country_name(&self.country),
+                self.country.alpha2
+            ),
+            self.total_time.num_days()
+        )
+    }
+}
+
+/// sums the visits to each country, sorted by descending total time with ties broken by alpha-2 code
+/// visits that are still ongoing are counted until end
+pub fn summarize_visits(visits: &[Visit], end: DateTime<Utc>) -> Vec<CountrySummary> {
+    let mut summaries: BTreeMap<&str, CountrySummary> = BTreeMap::new();
+    for visit in visits {
+        let exited_at = visit.exited_at.unwrap_or(end);
+        let summary = summaries
+            .entry(visit.country.alpha2)
+            .or_insert(CountrySummary {
+                country: visit.country,
+                total_time: TimeDelta::zero(),
+                first_seen: visit.entered_at,
+                last_seen: exited_at,
+                visit_count: 0,
+            });
+        summary.total_time += exited_at - visit.entered_at;
+        summary.first_seen = summary.first_seen.min(visit.entered_at);
+        summary.last_seen = summary.last_seen.max(exited_at);
+        summary.visit_count += 1;
+    }
+
+    // the map is ordered by alpha-2 code, and the stable sort preserves that order between ties
+    let mut summaries: Vec<CountrySummary> = summaries.into_values().collect();
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.total_time));
+    summaries
+}
+
+/// a calendar period that crossings can be grouped by
+#[derive(PartialEq, Eq, Copy, Clone, ValueEnum, Debug)]
+pub enum Period {
+    /// weeks starting on Monday
+    Week,
+    /// calendar months
+    Month,
+    /// calendar years
+    Year,
+}
+
+impl Period {
+    /// the first date of the period containing the date
+    pub fn start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Week => date - TimeDelta::days(date.weekday().num_days_from_monday() as i64),
+            Period::Month => date.with_day(1).unwrap(),
+            Period::Year => date.with_ordinal(1).unwrap(),
+        }
+    }
+
+    /// a label for the period starting on the date
+    pub fn label(&self, start: NaiveDate) -> String {
+        match self {
+            Period::Week => format!("Week of {}", start.format("%Y-%m-%d")),
+            Period::Month => start.format("%B %Y").to_string(),
+            Period::Year => start.format("%Y").to_string(),
+        }
+    }
+}
+
+/// splits the items into the calendar periods (UTC) of their timestamps, each labeled by the first date of its period
+/// periods without any items are omitted
+/// requires items to be sorted by timestamp
+pub fn partition_by_period<T: Clone>(
+    items: &[T],
+    period: Period,
+    timestamp: impl Fn(&T) -> DateTime<Utc>,
+) -> Vec<(NaiveDate, Vec<T>)> {
+    let mut partitions: Vec<(NaiveDate, Vec<T>)> = vec![];
+    for item in items {
+        let start = period.start(timestamp(item).date_naive());
+        // items are sorted, so the period of an item is either the last one or a new one
+        match partitions.last_mut() {
+            Some((last_start, partition)) if *last_start == start => partition.push(item.clone()),
+            _ => partitions.push((start, vec![item.clone()])),
+        }
+    }
+    partitions
+}
+
+/// the countries entered during a calendar period
+#[derive(Debug)]
+pub struct PeriodSummary {
+    pub period: Period,
+    /// the first date of the period
+    pub start: NaiveDate,
+    /// the countries entered during the period, in the order they were first entered
+    pub countries: Vec<CountryCode>,
+}
+
+impl Display for PeriodSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let countries = self
+            .countries
+            .iter()
+            .map(localized_country_name)
+            .collect::<Vec<&str>>()
+            .join(", ");
+        write!(f, "{}: {countries}", self.period.label(self.start))
+    }
+}
+
+/// groups the crossings by the calendar period (UTC) of their timestamps, listing the distinct countries entered in each
+/// periods without any crossings into a country are omitted
+/// requires crossings to be sorted by timestamp
+pub fn rollup(crossings: &[BorderCrossing], period: Period) -> Vec<PeriodSummary> {
+    let mut summaries: Vec<PeriodSummary> = vec![];
+    for crossing in crossings {
+        let start = period.start(crossing.timestamp.date_naive());
+        let countries = with_parent_countries(&crossing.new_regions)
+            .into_iter()
+            .filter_map(|r| match r {
+                Region::CountryCode(c) => Some(c),
+                _ => None,
+            });
+        for country in countries {
+            // crossings are sorted, so the period of a crossing is either the last one or a new one
+            let summary = match summaries.last_mut() {
+                Some(summary) if summary.start == start => summary,
+                _ => {
+                    summaries.push(PeriodSummary {
+                        period,
+                        start,
+                        countries: vec![],
+                    });
+                    summaries.last_mut().unwrap()
+                }
+            };
+            if !summary.countries.iter().any(|c| c.alpha2 == country.alpha2) {
+                summary.countries.push(country);
+            }
+        }
+    }
+    summaries
+}
@@ -0,0 +1,58 @@
+// this file contains the detection of changes in UTC offset from a sequence of records
+
+use std::fmt::{self, Display};
+
+use chrono::{DateTime, FixedOffset, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::core::data::{timezone_at, Record};
+
+/// an instance of the UTC offset at the location changing between one record and the next
+#[derive(Debug)]
+pub struct OffsetChange {
+    pub timestamp: DateTime<Utc>,
+    pub from: FixedOffset,
+    pub to: FixedOffset,
+    /// the timezone of the record at which the new offset was first seen
+    pub timezone: Tz,
+}
+
+impl Display for OffsetChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}  {} → {} ({})",
+            self.timestamp.to_rfc2822(),
+            self.from,
+            self.to,
+            self.timezone
+        )
+    }
+}
+
+/// lists every change in the UTC offset at the location of the records, regardless of the regions they are in
+/// changes within a timezone, such as the start and end of daylight saving time, are included, since the offset still changes
+/// records whose timezone isn't recognized are skipped
+/// requires records to be sorted by timestamp
+pub fn offset_changes(records: &[Record]) -> Vec<OffsetChange> {
+    let mut changes: Vec<OffsetChange> = vec![];
+    let mut prev_offset: Option<FixedOffset> = None;
+    for record in records {
+        let Some(timezone) = timezone_at(record.latitude, record.longitude) else {
+            continue;
+        };
+        let offset = timezone
+            .offset_from_utc_datetime(&record.timestamp.naive_utc())
+            .fix();
+        if let Some(from) = prev_offset.filter(|&p| p != offset) {
+            changes.push(OffsetChange {
+                timestamp: record.timestamp,
+                from,
+                to: offset,
+                timezone,
+            });
+        }
+        prev_offset = Some(offset);
+    }
+    changes
+}
@@ -0,0 +1,191 @@
+// this file contains the tracking of visits to countries across border crossings
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Display};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use rust_iso3166::CountryCode;
+
+use crate::core::data::{with_parent_countries, BorderCrossing, Region, MISSING_DATA_THRESHOLD};
+use crate::core::names::localized_country_name;
+
+/// for each crossing, the visit number of each country it enters (1 for the first entry, 2 for the first re-entry, etc)
+/// a country is entered when it is in a crossing's regions but not in the regions of the last crossing before it
+/// missing data does not end a visit, so returning to the same country after a gap is not counted as a re-entry
+/// requires crossings to be sorted by timestamp
+pub fn visit_numbers(crossings: &[BorderCrossing]) -> Vec<HashMap<Region, usize>> {
+    let mut visit_counts: HashMap<Region, usize> = HashMap::new();
+    let mut previous_regions: BTreeSet<Region> = BTreeSet::new();
+    crossings
+        .iter()
+        .map(|crossing| {
+            if crossing.new_regions.contains(&Region::MissingData) {
+                return HashMap::new();
+            }
+            let entered: HashMap<Region, usize> = crossing
+                .new_regions
+                .iter()
+                .filter(|r| matches!(r, Region::CountryCode(_)))
+                .filter(|r| !previous_regions.contains(r))
+                .map(|r| {
+                    let count = visit_counts.entry(r.clone()).or_default();
+                    *count += 1;
+                    (r.clone(), *count)
+                })
+                .collect();
+            previous_regions = crossing.new_regions.clone();
+            entered
+        })
+        .collect()
+}
+
+/// the first entry into a country
+#[derive(Debug)]
+pub struct FirstEntry {
+    pub country: CountryCode,
+    pub entered_at: DateTime<Utc>,
+}
+
+impl Display for FirstEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}  {}",
+            self.entered_at.format("%Y-%m-%d"),
+            localized_country_name(&self.country)
+        )
+    }
+}
+
+/// the crossings that first entered each country, discarding later re-entries
+/// requires crossings to be sorted by timestamp; the result is in the same order, with countries entered by the same crossing ordered by alpha-2 code
+pub fn first_entries(crossings: &[BorderCrossing]) -> Vec<FirstEntry> {
+    crossings
+        .iter()
+        .zip(visit_numbers(crossings))
+        .flat_map(|(crossing, numbers)| {
+            let mut entered: Vec<CountryCode> = numbers
+                .into_iter()
+                .filter(|(_, n)| *n == 1)
+                .filter_map(|(r, _)| match r {
+                    Region::CountryCode(c) => Some(c),
+                    _ => None,
+                })
+                .collect();
+            entered.sort_by_key(|c| c.alpha2);
+            entered.into_iter().map(|country| FirstEntry {
+                country,
+                entered_at: crossing.timestamp,
+            })
+        })
+        .collect()
+}
+
+/// the english ordinal of a number, e.g. 1st, 2nd, 3rd, 4th
+pub fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+/// a continuous stay in a country, from the crossing that entered it to the crossing that left it
+#[derive(Debug)]
+pub struct Visit {
+    pub country: CountryCode,
+    pub entered_at: DateTime<Utc>,
+    /// None if the visit is still ongoing at the end of the data
+    pub exited_at: Option<DateTime<Utc>>,
+}
+
+impl Visit {
+    pub fn duration(&self) -> Option<TimeDelta> {
+        self.exited_at.map(|exited_at| exited_at - self.entered_at)
+    }
+}
+
+impl Display for Visit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exited_string = match self.exited_at {
+            Some(exited_at) => format!("    | Exited: {}", exited_at.to_rfc2822()),
+            None => "    | Exited: Ongoing".to_string(),
+        };
+        let duration_string = match self.duration() {
+            Some(duration) => format!("    | Duration: {} Days", duration.num_days()),
+            None => "    | Duration Unknown".to_string(),
+        };
+        write!(
+            f,
+            "{}\n    | Entered: {}\n{exited_string}\n{duration_string}\n    |\n",
+            localized_country_name(&self.country),
+            self.entered_at.to_rfc2822()
+        )
+    }
+}
+
+/// pairs each entry into a country with the crossing that next leaves it, producing one visit per stay
+/// missing data is attributed to the visit when the data resumes in the same country, so a gap does not split a visit
+/// when the data resumes elsewhere, the visit ends at the last record before the gap
+/// requires crossings to be sorted by timestamp; the result is sorted by entry time
+pub fn visits(crossings: &[BorderCrossing]) -> Vec<Visit> {
+    // open visits by alpha-2 code, along with the time they would end if the data resumes elsewhere after a gap
+    let mut open: BTreeMap<&str, (Visit, Option<DateTime<Utc>>)> = BTreeMap::new();
+    let mut closed: Vec<Visit> = vec![];
+    for crossing in crossings {
+        if crossing.new_regions.contains(&Region::MissingData) {
+            // the missing data crossing is stamped one threshold after the last record
+            let last_seen = crossing.timestamp - MISSING_DATA_THRESHOLD;
+            for (_, pending_exit) in open.values_mut() {
+                pending_exit.get_or_insert(last_seen);
+            }
+            continue;
+        }
+
+        let countries: Vec<CountryCode> = with_parent_countries(&crossing.new_regions)
+            .into_iter()
+            .filter_map(|r| match r {
+                Region::CountryCode(c) => Some(c),
+                _ => None,
+            })
+            .collect();
+
+        // close the visits to countries that were left
+        let left: Vec<&str> = open
+            .keys()
+            .filter(|code| !countries.iter().any(|c| c.alpha2 == **code))
+            .copied()
+            .collect();
+        for code in left {
+            if let Some((mut visit, pending_exit)) = open.remove(code) {
+                visit.exited_at = Some(pending_exit.unwrap_or(crossing.timestamp));
+                closed.push(visit);
+            }
+        }
+
+        // continue the visits to countries that are still occupied, and open visits to new ones
+        for country in countries {
+            open.entry(country.alpha2)
+                .and_modify(|(_, pending_exit)| *pending_exit = None)
+                .or_insert((
+                    Visit {
+                        country,
+                        entered_at: crossing.timestamp,
+                        exited_at: None,
+                    },
+                    None,
+                ));
+        }
+    }
+
+    // visits still open at the end are ongoing, unless the data ended in a gap
+    closed.extend(open.into_values().map(|(mut visit, pending_exit)| {
+        visit.exited_at = pending_exit;
+        visit
+    }));
+    closed.sort_by_key(|v| v.entered_at);
+    closed
+}
@@ -1,65 +1,691 @@
-mod core;
-
-use crate::core::data::*;
-use crate::core::json::*;
-use chrono::TimeDelta;
-use clap::{Parser, Subcommand};
-use std::collections::HashSet;
-use std::{ffi::OsStr, fs, io::Read, path::PathBuf};
-use zip::ZipArchive;
+use chrono::{Datelike, FixedOffset, NaiveTime, TimeDelta};
+use clap::error::ErrorKind;
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::{generate, Shell};
+use log::{error, info, warn, LevelFilter};
+use rust_iso3166::CountryCode;
+use std::collections::{BTreeSet, HashSet};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use std::{fs, path::PathBuf};
+use takeout_travel_history::core::cache::*;
+use takeout_travel_history::core::calendar::*;
+use takeout_travel_history::core::countries::*;
+use takeout_travel_history::core::crossings::*;
+use takeout_travel_history::core::data::*;
+use takeout_travel_history::core::display::*;
+use takeout_travel_history::core::doctor::*;
+use takeout_travel_history::core::input::*;
+use takeout_travel_history::core::instability::*;
+use takeout_travel_history::core::names::*;
+use takeout_travel_history::core::occupancy::*;
+use takeout_travel_history::core::output::*;
+use takeout_travel_history::core::residency::*;
+use takeout_travel_history::core::schengen::*;
+use takeout_travel_history::core::segments::*;
+use takeout_travel_history::core::semantic::*;
+use takeout_travel_history::core::state::*;
+use takeout_travel_history::core::stats::*;
+use takeout_travel_history::core::stays::*;
+use takeout_travel_history::core::summary::*;
+use takeout_travel_history::core::timezones::*;
+use takeout_travel_history::core::visits::*;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    #[arg(
+        short('o'),
+        long,
+        global(true),
+        required(false),
+        help("Writes the command's output to the given file instead of stdout, creating or truncating it")
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        short('v'),
+        long,
+        global(true),
+        action(ArgAction::Count),
+        help("Logs diagnostics to stderr; can be specified multiple times for more detail")
+    )]
+    verbose: u8,
+    #[arg(
+        short('q'),
+        long,
+        global(true),
+        required(false),
+        conflicts_with("verbose"),
+        help("Logs nothing but errors to stderr")
+    )]
+    quiet: bool,
+    #[arg(short('z'), long, global(true), required(false), help("Compresses the output file, or each file of --output-dir, with gzip, appending .gz to the file name if not already present"))]
+    compress: bool,
+    #[arg(
+        long,
+        global(true),
+        required(false),
+        default_value("high"),
+        help("The grid resolution of the country boundaries dataset; lower resolutions use less memory but misclassify more coordinates near borders")
+    )]
+    boundary_resolution: BoundaryResolution,
+    #[arg(
+        long,
+        global(true),
+        required(false),
+        help("Reads timestamps recorded without an offset as UTC; this is the default")
+    )]
+    assume_utc: bool,
+    #[arg(long, global(true), required(false), conflicts_with("assume_utc"), help("Reads timestamps recorded without an offset as the local time at the record's coordinates, or as UTC where the timezone isn't recognized"))]
+    assume_local: bool,
+    #[arg(long, global(true), required(false), default_value("en"), help("The language to display country names in; countries without a translation are displayed in English"))]
+    lang: Language,
+    #[arg(long, global(true), required(false), help("Includes records in the open ocean, outside of any region, which every command otherwise excludes; the cache command always keeps them"))]
+    keep_ocean: bool,
+}
+
+/// the exports that a command reads its records from
+#[derive(Args)]
+struct InputArgs {
+    #[arg(
+        short('p'),
+        long,
+        required(true),
+        help("The .zip, .tgz, or .json file that will be read to produce the command's output, or - to read json from stdin; a directory reads the extracted Records.json within it, and a directory or a glob such as takeout-*.zip otherwise reads whichever archive parts contain Records.json; can be specified multiple times to merge several exports")
+    )]
+    path: Vec<PathBuf>,
 }
 
+// the commands are parsed once per run, so the size of the largest variant doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// lists every time the location crosses a recognized border
     BorderCrossings {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(short('e'), long, visible_alias("drop-source"), required(false), value_name("SOURCE"), ignore_case(true), help("Excludes a certain data source from the results; can be specified multiple times to exclude multiple sources; none excludes the records without a recorded source, which are often interpolated"))]
+        exclude_source: Vec<Source>,
+        #[arg(long, required(false), help("Excludes the semantic and manual sources (visit-arrival, visit-departure, manual), keeping only the raw signals recorded by the device"))]
+        raw_signals_only: bool,
+        #[arg(short('r'), long, required(false), value_name("CODE"), value_parser(parse_region_code), help("Excludes border crossings solely into a certain region, given as an ISO 3166 code such as US or US-CA; can be specified multiple times to exclude multiple regions"))]
+        exclude_region: Vec<Region>,
+        #[arg(long, required(false), value_name("CODE"), value_parser(parse_subdivision_code), help("Strips a certain subdivision, given as an ISO 3166-2 code such as US-CA, from every crossing, while keeping other subdivision changes; can be specified multiple times to strip multiple subdivisions"))]
+        exclude_subdivision: Vec<Region>,
+        #[arg(long, required(false), value_name("CODE"), value_parser(parse_country_code), help("Only includes border crossings into or out of a certain country, given as an ISO 3166 alpha-2 code such as JP"))]
+        country: Option<CountryCode>,
+        #[arg(long, required(false), num_args(4), allow_negative_numbers(true), value_names(["MIN_LAT", "MIN_LON", "MAX_LAT", "MAX_LON"]), help("Only includes records within the given box of coordinates; if MIN_LON is greater than MAX_LON, the box wraps across the antimeridian"))]
+        bbox: Option<Vec<f64>>,
+        #[arg(long, required(false), help("Excludes records at exactly (0, 0), which corrupt exports use in place of a missing fix"))]
+        drop_null_island: bool,
+        #[arg(long, required(false), help("Treats records in the open ocean as being in international waters, so that crossings into and out of it are listed; implies --keep-ocean"))]
+        track_ocean: bool,
+        #[arg(long, required(false), value_name("DURATION"), value_parser(parse_duration), help("Keeps at most one record per window of the given duration, such as 30s or 5m, while the records stay at the same spot; records that move are always kept"))]
+        dedupe_window: Option<TimeDelta>,
+        #[arg(long, required(false), value_name("N"), help("Excludes every record from any calendar day (in UTC) with fewer than N records, which removes isolated stray fixes in sparse histories"))]
+        min_records_per_day: Option<usize>,
+        #[arg(long, required(false), value_name("CONFIDENCE"), value_parser(parse_confidence), help("Excludes records whose accuracy circle straddles a boundary, keeping only those with at least this share, from 0 to 1, of the points on their accuracy circle in the same regions as their center"))]
+        min_confidence: Option<f64>,
+        #[arg(short('s'), long, required(false), help("Ignores border crossings between subregions such as US states, Canadian provinces, etc; where the boundary data has a subdivision but not its country, the subdivision counts as being in its parent country"))]
+        ignore_subregions: bool,
+        #[arg(long, required(false), conflicts_with("ignore_subregions"), help("Only detects crossings between countries, by reducing each record's regions to its countries before detection; where the boundary data has a subdivision but not its country, the subdivision's parent country is used"))]
+        countries_only: bool,
+        #[arg(long, required(false), conflicts_with_all(["ignore_subregions", "countries_only"]), help("Only reports international crossings, ignoring all movement within a country such as between US states; the same as --countries-only, and the recommended mode for a plain travel history"))]
+        only_international: bool,
+        #[arg(long, required(false), conflicts_with_all(["ignore_subregions", "countries_only", "only_international"]), help("Replaces each subdivision with its country before detection, so that entering a subdivision is reported as entering its country and moving between subdivisions of one country is not a crossing"))]
+        collapse_subdivisions_to_country: bool,
+        #[arg(long, required(false), help("Reduces each record's regions to a single country before detection; where a point resolves to several countries, the one with the lowest alpha-2 code is kept, and subdivisions are dropped in favor of their country"))]
+        primary_only: bool,
+        #[arg(long, required(false), conflicts_with("primary_only"), help("Treats a record that the boundary data puts in several countries at once, such as at a tripoint, as being in a border zone between them, rendered as \"Border Zone: A/B\"; entering a border zone from one of its countries is not a crossing, so a crossing is only reported once the record is in a single country again"))]
+        border_zones: bool,
+        #[arg(long, required(false), help("Replaces obsolete countries with their present-day successor, where there is exactly one; obsolete countries that were split are left as-is"))]
+        remap_obsolete: bool,
+        #[arg(short('m'), long, required(false), help("Does not treat missing data as its own region and instead assumes that the region remains the same for the duration of missing data"))]
+        ignore_missing_data: bool,
+        #[arg(long, required(false), help("Does not treat a gap in the data as missing data when the records on either side of it are in the same regions; gaps across a change of region are still reported"))]
+        interpolate_missing: bool,
+        #[arg(long, required(false), requires("interpolate_missing"), value_name("DURATION"), value_parser(parse_duration), help("Only interpolates gaps shorter than the given duration, such as 12h or 7d; longer gaps are still treated as missing data"))]
+        max_gap_fill: Option<TimeDelta>,
+        #[arg(long, required(false), help("Does not merge consecutive crossings into the same regions, which can be left behind by the other filters"))]
+        no_collapse: bool,
+        #[arg(
+            short('f'),
+            long,
+            required(false),
+            default_value("text"),
+            help("The format of the command's output")
+        )]
+        format: OutputFormat,
+        #[arg(
+            long,
+            required(false),
+            help("Indents json output for human inspection instead of writing it compactly")
+        )]
+        json_pretty: bool,
+        #[arg(
+            long,
+            required(false),
+            value_name("N"),
+            default_value_t = DEFAULT_COORD_PRECISION,
+            value_parser(clap::value_parser!(u32).range(0..=15)),
+            help("The number of decimal places latitudes and longitudes are rounded to in the kepler, owntracks, and gpx formats; the default of 6 is about 10 cm, and fewer hide the exact places in a shared track")
+        )]
+        coord_precision: u32,
+        #[arg(long, required(false), help("Adds the great-circle path across each missing data gap to the gpx format, as a separate track of type \"interpolated\" without timestamps, so that mapping tools can show the probable path without it passing for recorded points"))]
+        interpolate_track: bool,
+        #[arg(
+            long,
+            required(false),
+            value_name("DURATION"),
+            value_parser(parse_duration),
+            help("Blurs the coordinates of every place where at least the given duration was spent, such as 8h, to about 1 km in the output, so that a shared export doesn't reveal a home or workplace; crossings are detected from the exact coordinates first")
+        )]
+        redact_dwell: Option<TimeDelta>,
+        #[arg(
+            long,
+            required(false),
+            value_name("PATH"),
+            help("Reads the flights from a Semantic Location History export, as accepted by semantic-visits, and marks each crossing made during or within 6 hours after a flight, which corroborates it as a real international move: a corroborated crossing is never dropped by --border-buffer, and its flight is shown in the text, json, jsonl, markdown, and table formats; can be specified multiple times")
+        )]
+        infer_from_activity: Vec<PathBuf>,
+        #[arg(
+            long,
+            required(false),
+            value_name("PATH"),
+            help("Only reports the crossings after the latest record processed by the last run with this state file, then stores the latest record of this run in it, for producing an incremental travel log from a recurring job; a state file that doesn't exist yet processes every record")
+        )]
+        state_file: Option<PathBuf>,
+        #[arg(long, required(false), requires("output_dir"), help("Writes the crossings of each year (UTC) to its own file in --output-dir, named crossings-<year> with the extension of the format; the last crossing of each year keeps its duration until the first crossing of the next year, and --sort and --limit apply to each file"))]
+        split_by_year: bool,
+        #[arg(long, required(false), value_name("DIR"), requires("split_by_year"), help("The directory the files of --split-by-year are written to, which is created if it doesn't exist; replaces --output"))]
+        output_dir: Option<PathBuf>,
+        #[arg(long, required(false), value_name("KM"), help("Only reports a move into new regions once at least this many kilometers are traveled within them, suppressing crossings where the track merely grazes a border; the distance is the length of the path through the records until the next crossing, so jitter while staying put also counts towards it, and regions entered for a single record are never reported"))]
+        border_buffer: Option<f64>,
+        #[arg(short('l'), long, required(false), help("Displays timestamps in the local timezone of each crossing's coordinates instead of UTC"))]
+        local_time: bool,
+        #[arg(
+            long,
+            required(false),
+            value_name("OFFSET"),
+            value_parser(parse_utc_offset),
+            conflicts_with("local_time"),
+            allow_hyphen_values(true),
+            help("Displays timestamps at a fixed offset from UTC, such as +09:00, instead of UTC")
+        )]
+        utc_offset: Option<FixedOffset>,
+        #[arg(
+            long,
+            required(false),
+            help(
+                "Annotates each country entry as a first entry or a re-entry with its visit count"
+            )
+        )]
+        show_visit_count: bool,
+        #[arg(
+            long,
+            required(false),
+            help("Displays the ISO code of each region after its name, e.g. California (US-CA)")
+        )]
+        show_codes: bool,
+        #[arg(
+            long,
+            required(false),
+            help("Prefixes each country with its flag emoji")
+        )]
+        flags: bool,
+        #[arg(
+            long,
+            required(false),
+            help("Displays the coordinates of the record that triggered each crossing")
+        )]
+        show_coords: bool,
+        #[arg(long, required(false), help("Displays the source of the record that triggered each crossing, such as GPS or Cell"))]
+        show_source: bool,
+        #[arg(
+            long,
+            required(false),
+            default_value("asc"),
+            help("The order to list crossings in, in every format that lists them; durations are always the time until the chronologically next crossing")
+        )]
+        sort: SortOrder,
+        #[arg(long, required(false), help("Renders a subdivision on the same line as its country, such as \"United States of America / California\", when both are entered in the same crossing"))]
+        combine_subdivisions: bool,
+        #[arg(long, required(false), help("Annotates each crossing with why it was emitted: first-record, region-change, gap>threshold (missing data), or post-gap-resolution (the first record after missing data)"))]
+        explain: bool,
+        #[arg(long, required(false), help("Prints the wall-clock time taken by each stage of the pipeline to stderr; regions are then resolved in a stage of their own, ahead of filtering"))]
+        timings: bool,
+        #[arg(long, required(false), value_name("N"), help("Outputs at most the first N crossings in the order of --sort, after all filtering; text output notes how many were left out, and every crossing output keeps its duration until the next crossing, even one left out"))]
+        limit: Option<usize>,
+        #[arg(long, required(false), value_name("ATTRIBUTE"), help("Instead of listing the crossings, counts the crossings by an attribute, such as the source of the record that triggered each one"))]
+        group_by: Option<GroupBy>,
+        #[arg(long, required(false), value_name("FILE"), num_args(0..=1), default_missing_value("-"), help("Logs the decision made for each record during crossing detection to the given file, or to stderr if no file is given"))]
+        trace: Option<PathBuf>,
+        #[arg(long, required(false), help("Instead of border crossings, reports consecutive records that are nearly identical in position but classify to different regions"))]
+        report_instability: bool,
+        #[arg(long, required(false), value_name("METERS"), default_value_t = DEFAULT_INSTABILITY_RADIUS_M, requires("report_instability"), help("The distance under which two records are considered nearly identical when reporting instability"))]
+        instability_radius: f64,
+    },
+    /// summarizes the distance traveled, countries visited, border crossings, and dates covered
+    Stats {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// lists every country that the location history never enters
+    CountriesNotVisited {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(
+            short('c'),
+            long,
+            required(false),
+            help("Only lists countries on the given continent")
+        )]
+        continent: Option<Continent>,
+    },
+    /// counts the days spent in the Schengen area in each trailing 180-day window, flagging stays over the 90-day limit
+    Schengen {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// renders a calendar of each year, with each day labeled by the country in which the most time was spent
+    Calendar {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// tabulates the days present in each country in each year
+    Residency {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(long, required(false), help("Outputs the table as CSV"))]
+        csv: bool,
+        #[arg(long, required(false), value_name("HH:MM"), default_value("00:00"), value_parser(parse_time_of_day), help("The time of day (UTC) at which each day starts; a day is labeled by the date on which it starts"))]
+        day_boundary: NaiveTime,
+        #[arg(long, required(false), default_value("any"), help("How a day is attributed to countries: any counts it for every country occupied at any point during it, majority counts it only for the country in which the most time was spent during it, with ties going to the lowest alpha-2 code"))]
+        day_attribution: DayAttribution,
+    },
+    /// clusters consecutive nearby records into the places where a while was spent
+    Stays {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(long, required(false), value_name("METERS"), default_value_t = DEFAULT_STAY_RADIUS_M, help("The distance from a stay's center within which records are part of the stay"))]
+        radius: f64,
+        #[arg(
+            long,
+            required(false),
+            value_name("DURATION"),
+            value_parser(parse_duration),
+            default_value("20m"),
+            help("The shortest time spent in one place that counts as a stay, such as 20m or 2h")
+        )]
+        min_dwell: TimeDelta,
+    },
+    /// lists the places visited according to the Semantic Location History, with how long each visit lasted
+    SemanticVisits {
         #[arg(
             short('p'),
             long,
             required(true),
-            help("The .zip or .json file that will be read to produce the command's output")
+            help("A monthly Semantic Location History .json file such as 2023_MAY.json, a directory containing them, or a .zip or .tgz export; can be specified multiple times to merge several exports")
+        )]
+        path: Vec<PathBuf>,
+    },
+    /// lists each continuous stay in a set of regions, with its start, end, and duration
+    Segments {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// lists each visit to a country, from entry to exit
+    Visits {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(
+            long,
+            required(false),
+            help(
+                "Only lists the date of the first entry into each country, discarding re-entries"
+            )
+        )]
+        first_entry_only: bool,
+    },
+    /// lists the cumulative time spent in each country, most time first
+    TimeInRegion {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(
+            short('n'),
+            long,
+            required(false),
+            value_name("N"),
+            help("Only lists the N countries with the most time spent")
+        )]
+        top: Option<usize>,
+        #[arg(
+            long,
+            required(false),
+            value_name("PERIOD"),
+            conflicts_with("top"),
+            help("Instead lists the distinct countries entered in each calendar period")
+        )]
+        rollup: Option<Period>,
+        #[arg(
+            short('f'),
+            long,
+            required(false),
+            default_value("text"),
+            conflicts_with("rollup"),
+            help("The output format of the per-country summaries")
+        )]
+        format: SummaryFormat,
+        #[arg(
+            long,
+            required(false),
+            help("Indents json output for human inspection instead of writing it compactly")
+        )]
+        json_pretty: bool,
+    },
+    /// lists the border crossings in a newer export that come after the last crossing of an older one
+    Diff {
+        #[arg(
+            long,
+            required(true),
+            help("The older export, in any of the forms accepted by --path of the other commands: a .zip, .tgz, or .json file, - for json from stdin, a directory, or a glob; can be specified multiple times to merge several exports")
+        )]
+        old: Vec<PathBuf>,
+        #[arg(
+            long,
+            required(true),
+            help("The newer export, in any of the forms accepted by --path of the other commands: a .zip, .tgz, or .json file, - for json from stdin, a directory, or a glob; can be specified multiple times to merge several exports")
+        )]
+        new: Vec<PathBuf>,
+    },
+    /// lists every change in UTC offset at the location, with the offsets before and after, regardless of country
+    TimezoneChanges {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// writes the parsed records, with the regions they resolve to, to a cache that can be read in place of the export
+    /// the cache is read by passing it as a path to any command; its file name must end in .cache.json
+    Cache {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// prints a completion script for the given shell
+    #[command(hide(true))]
+    Completions {
+        #[arg(help("The shell to generate the completion script for"))]
+        shell: Shell,
+    },
+    /// audits an export without detecting crossings, reporting its format, record counts, date span, sources, and gaps
+    Doctor {
+        #[arg(
+            short('p'),
+            long,
+            required(true),
+            help("The .zip, .tgz, or .json file to audit, or - to read json from stdin; a directory audits the extracted Records.json or each archive part within it")
         )]
         path: PathBuf,
-        #[arg(short('e'), long, required(false), value_name("SOURCE"), help("Excludes a certain data source from the results; can be specified multiple times to exclude multiple sources"))]
-        exclude_source: Vec<Source>,
-        #[arg(short('s'), long, required(false), help("Ignores border crossings between subregions such as US states, Canadian provinces, etc"))]
-        ignore_subregions: bool,
-        #[arg(short('m'), long, required(false), help("Does not treat missing data as its own region and instead assumes that the region remains the same for the duration of missing data"))]
-        ignore_missing_data: bool,
+    },
+    /// prints the regions a coordinate maps to in the boundary data, for verifying crossings
+    #[command(allow_negative_numbers(true))]
+    Geocode {
+        #[arg(help("The latitude of the coordinate, in degrees"))]
+        latitude: f64,
+        #[arg(help("The longitude of the coordinate, in degrees"))]
+        longitude: f64,
     },
 }
 
-fn main() {
-    run_cli();
+/// the exit code when an input file contains records but none of them are usable
+const EXIT_NO_USABLE_RECORDS: u8 = 2;
+
+/// exits with 0 on success, including when there are no results, or EXIT_NO_USABLE_RECORDS if an input file had no usable records
+/// panics, such as on a file that can't be read, exit with Rust's panic code of 101
+fn main() -> ExitCode {
+    match run_cli() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(code) => code,
+    }
 }
 
-fn run_cli() {
+fn run_cli() -> Result<(), ExitCode> {
     let cli = Cli::parse();
+    // --output-dir belongs to a subcommand, so clap can't require it of the global --compress
+    let writes_output_dir = matches!(
+        &cli.command,
+        Some(Commands::BorderCrossings {
+            output_dir: Some(_),
+            ..
+        })
+    );
+    if cli.compress && cli.output.is_none() && !writes_output_dir {
+        Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "--compress requires --output or --output-dir",
+            )
+            .exit();
+    }
+    init_logging(cli.verbose, cli.quiet);
+    load_boundaries(cli.boundary_resolution);
+    set_language(cli.lang);
+    if cli.assume_local {
+        assume_naive_timestamp_zone(NaiveTimestampZone::Local);
+    }
     match &cli.command {
         Some(Commands::BorderCrossings {
-            path,
+            input: InputArgs { path },
             exclude_source,
+            raw_signals_only,
+            exclude_region,
+            exclude_subdivision,
+            country,
+            bbox,
+            drop_null_island,
+            track_ocean,
+            dedupe_window,
+            min_records_per_day,
+            min_confidence,
             ignore_subregions,
+            countries_only,
+            only_international,
+            collapse_subdivisions_to_country,
+            primary_only,
+            border_zones,
+            remap_obsolete,
             ignore_missing_data,
+            interpolate_missing,
+            max_gap_fill,
+            no_collapse,
+            format,
+            json_pretty,
+            coord_precision,
+            interpolate_track,
+            redact_dwell,
+            infer_from_activity,
+            state_file,
+            split_by_year,
+            output_dir,
+            border_buffer,
+            local_time,
+            utc_offset,
+            show_visit_count,
+            show_codes,
+            flags,
+            show_coords,
+            show_source,
+            sort,
+            combine_subdivisions,
+            explain,
+            timings,
+            limit,
+            group_by,
+            trace,
+            report_instability,
+            instability_radius,
         }) => {
-            // read file to Vec<Record>
-            let mut records: Vec<Record> = read_records_from_file(path);
+            let mut timer = StageTimer::new(*timings);
+
+            // read files to Vec<Record>
+            // chosen source types are excluded while reading, before records sharing a timestamp are deduplicated
+            let mut excluded_sources: HashSet<Source> = exclude_source.iter().copied().collect();
+            if *raw_signals_only {
+                excluded_sources
+                    .extend(Source::value_variants().iter().filter(|s| s.is_semantic()));
+            }
+            let path = &expand_paths(path);
+            let (mut records, parse_stats) = read_records_with_stats(path, &excluded_sources);
+            check_usable(path, &parse_stats)?;
+            timer.lap("read+parse");
+
+            // when timing, resolve every record's regions up front, so that geocoding isn't counted towards the stages that use them
+            if *timings {
+                for record in &records {
+                    preload_regions(record, record.regions());
+                }
+                timer.lap("geocode");
+            }
+
+            // optionally exclude records outside the bounding box
+            if let Some([min_latitude, min_longitude, max_latitude, max_longitude]) =
+                bbox.as_deref()
+            {
+                let bounding_box = BoundingBox {
+                    min_latitude: *min_latitude,
+                    min_longitude: *min_longitude,
+                    max_latitude: *max_latitude,
+                    max_longitude: *max_longitude,
+                };
+                let record_count = records.len();
+                records.retain(|r| bounding_box.contains(r));
+                info!(
+                    "excluded {} records outside the bounding box",
+                    record_count - records.len()
+                );
+            }
+
+            // optionally exclude null island artifacts
+            if *drop_null_island {
+                let record_count = records.len();
+                records.retain(|r| !r.is_null_island());
+                info!(
+                    "excluded {} records at null island",
+                    record_count - records.len()
+                );
+            }
+
+            // exclude records in the open ocean, unless they were requested
+            if !cli.keep_ocean && !*track_ocean {
+                drop_ocean_records(&mut records);
+            }
+
+            // optionally exclude days with too few records, before thinning reduces the counts
+            if let Some(min) = min_records_per_day {
+                let record_count = records.len();
+                records = drop_sparse_days(&records, *min);
+                info!(
+                    "excluded {} records on days with fewer than {min} records",
+                    record_count - records.len()
+                );
+            }
+
+            // records are already sorted chronologically by read_records_from_files, and filtering preserves the order
 
-            // exclude chosen source types
-            let excluded_sources: HashSet<&Source> = exclude_source.iter().collect();
-            records.retain(|r| !excluded_sources.contains(&r.source));
+            // optionally thin out rapid fixes at the same spot
+            if let Some(window) = dedupe_window {
+                let record_count = records.len();
+                records = thin_records(&records, *window);
+                info!(
+                    "thinned {} stationary records",
+                    record_count - records.len()
+                );
+            }
+
+            // optionally exclude records that are imprecise near a boundary, after thinning since each check is several lookups
+            if let Some(min) = min_confidence {
+                let record_count = records.len();
+                records.retain(|r| r.confidence() >= *min);
+                info!(
+                    "excluded {} records with a confidence below {min}",
+                    record_count - records.len()
+                );
+            }
+
+            // optionally report unstable classifications instead of crossings
+            if *report_instability {
+                let s = find_instabilities(&records, *instability_radius)
+                    .iter()
+                    .map(|i| i.to_string())
+                    .collect::<String>();
+                write_output(&s, &cli.output, cli.compress);
+                return Ok(());
+            }
+
+            // optionally skip the records processed by the last run, keeping the latest of them for detection to compare against
+            let latest_timestamp = records.last().map(|r| r.timestamp);
+            let cutoff = state_file.as_deref().and_then(read_last_timestamp);
+            if let Some(cutoff) = cutoff {
+                let record_count = records.len();
+                records = records_since(&records, cutoff);
+                info!(
+                    "skipped {} records processed by the last run, up to {}",
+                    record_count - records.len(),
+                    cutoff.to_rfc3339()
+                );
+            }
 
-            // sort records by timestamp in ascending order (should already be sorted, but just in case)
-            records.sort_unstable_by_key(|r| r.timestamp);
+            timer.lap("filter");
 
             // convert Record to BorderCrossing
-            let mut crossings = records_to_border_crossings(&records);
+            let detection_options = DetectionOptions {
+                interpolate_missing: *interpolate_missing,
+                max_gap_fill: *max_gap_fill,
+                countries_only: *countries_only || *only_international,
+                collapse_subdivisions: *collapse_subdivisions_to_country,
+                primary_only: *primary_only,
+                track_ocean: *track_ocean,
+                border_zones: *border_zones,
+            };
+            let mut crossings = match trace {
+                Some(trace_path) => {
+                    let mut trace_lines: Vec<String> = vec![];
+                    let crossings = detect_border_crossings(
+                        &records,
+                        &detection_options,
+                        Some(&mut trace_lines),
+                    );
+                    write_trace(&trace_lines, trace_path);
+                    crossings
+                }
+                None => detect_border_crossings(&records, &detection_options, None),
+            };
+
+            // optionally corroborate the crossings with the flights of the Semantic Location History, before the filters that spare them
+            if !infer_from_activity.is_empty() {
+                let flights = read_flights(infer_from_activity);
+                let corroborated = corroborate_crossings(&mut crossings, &flights);
+                info!(
+                    "{corroborated} of {} crossings coincide with one of {} flights",
+                    crossings.len(),
+                    flights.len()
+                );
+            }
+
+            // optionally drop region changes that don't reach far enough into the regions entered
+            if let Some(buffer_km) = border_buffer {
+                let crossing_count = crossings.len();
+                crossings = drop_shallow_crossings(&crossings, &records, *buffer_km);
+                info!(
+                    "dropped {} crossings less than {buffer_km} km into the regions entered",
+                    crossing_count - crossings.len()
+                );
+            }
+
+            // optionally replace obsolete countries with their successors
+            if *remap_obsolete {
+                remap_obsolete_regions(&mut crossings);
+            }
 
             // optionally strip missing data border crossings
             if *ignore_missing_data {
@@ -68,171 +694,591 @@ fn run_cli() {
 
             // optionally strip subregion crossings
             if *ignore_subregions {
-                // get the new regions and the old regions and compare the differing Regions
-                // if any of the differing Regions are NOT a subregion, then we can keep the crossing because it is not solely between subregions
+                crossings = drop_subregion_crossings(&crossings);
+            }
+
+            // optionally strip excluded subdivisions from each crossing, leaving the crossing itself to be collapsed if nothing else changed
+            if !exclude_subdivision.is_empty() {
+                for crossing in crossings.iter_mut() {
+                    crossing
+                        .new_regions
+                        .retain(|r| !exclude_subdivision.contains(r));
+                }
+            }
+
+            // optionally strip crossings into excluded regions
+            if !exclude_region.is_empty() {
+                // a crossing is kept if any of the regions it newly enters is not excluded
+                // subdivisions of an excluded country are excluded along with it
+                let excluded_regions: HashSet<&Region> = exclude_region.iter().collect();
+                let is_excluded = |r: &Region| {
+                    excluded_regions.contains(r)
+                        || r.parent_country()
+                            .is_some_and(|c| excluded_regions.contains(&Region::CountryCode(c)))
+                };
                 crossings = compare_and_retain(&crossings, |c, p| {
                     let differing_regions = &c.new_regions - &p.new_regions;
-                    differing_regions.iter().any(|r| !r.is_subregion())
+                    differing_regions.is_empty()
+                        || differing_regions.iter().any(|r| !is_excluded(r))
                 })
             }
 
-            // strip consecutive duplicates
-            // now that we've potentially stripped out certain types of border crossings, we may have crossings next to each other that no longer differ
-            // consider the original data [Muffintown, Missing Data, Muffintown]; if we strip Missing Data, we're now left with [Muffintown, Muffintown] as two separate, consecutive border crossings
-            // to fix this issue, we strip consecutive duplicates from the data here
-            crossings = compare_and_retain(&crossings, |c, p| {
-                (&c.new_regions - &p.new_regions).len() > 0 // if the crossing entries' regions differ by at least one, the crossing can be retained
-            });
+            // optionally keep only the crossings into or out of a country
+            if let Some(country) = country {
+                // a crossing touches the country if exactly one of it and the crossing before it is in the country
+                let focus = Region::CountryCode(*country);
+                let mut previous_regions: BTreeSet<Region> = BTreeSet::new();
+                crossings.retain(|c| {
+                    let regions = with_parent_countries(&c.new_regions);
+                    let touches = regions.contains(&focus) != previous_regions.contains(&focus);
+                    previous_regions = regions;
+                    touches
+                });
+            }
+
+            // strip consecutive duplicates, unless the raw pipeline output was requested
+            if !*no_collapse {
+                crossings = collapse_consecutive_duplicates(&crossings);
+            }
+
+            // crossings up to the cutoff were reported by the last run
+            if let Some(cutoff) = cutoff {
+                crossings.retain(|c| c.timestamp > cutoff);
+            }
+
+            timer.lap("detection");
+
+            // optionally count the crossings instead of listing them
+            if let Some(GroupBy::Source) = group_by {
+                let s = crossings_by_source(&crossings)
+                    .iter()
+                    .filter(|(_, count)| *count > 0)
+                    .map(|(source, count)| format!("{source:?}: {count}"))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                write_output(&s, &cli.output, cli.compress);
+                if let (Some(path), Some(latest)) = (state_file, latest_timestamp) {
+                    write_last_timestamp(path, cutoff.map_or(latest, |c| c.max(latest)));
+                }
+                return Ok(());
+            }
 
             // display border crossing data
-            let s = display_border_crossings(&crossings);
-            println!("{s}");
+            // an empty result is otherwise indistinguishable from a file that couldn't be read
+            if crossings.is_empty() {
+                warn!(
+                    "No border crossings found ({} records read, {} usable)",
+                    parse_stats.iter().map(|s| s.total).sum::<usize>(),
+                    records.len()
+                );
+            }
+
+            // optionally blur long stays, now that the exact coordinates are no longer needed for detection
+            if let Some(min_dwell) = redact_dwell {
+                let stays = redact_long_stays(&mut records, DEFAULT_STAY_RADIUS_M, *min_dwell);
+                redact_crossings(&mut crossings, &stays);
+                info!(
+                    "redacted {} stays of at least {}h",
+                    stays.len(),
+                    min_dwell.num_hours()
+                );
+            }
+
+            // text output notes the crossings left out itself
+            if let Some(n) = limit.filter(|n| *n < crossings.len() && *format != OutputFormat::Text)
+            {
+                warn!("showing the first {n} of {} crossings", crossings.len());
+            }
+
+            // the crossings may be one year of a split, followed by the first crossing of the next year
+            let render = |crossings: &[BorderCrossing],
+                          following: Option<&BorderCrossing>,
+                          records: &[Record]| {
+                // every format lists the same crossings: sorted, then limited, with the durations of those left out still known
+                let listing = Listing {
+                    sort: *sort,
+                    limit: *limit,
+                    following,
+                };
+                match format {
+                    OutputFormat::Text => {
+                        let options = DisplayOptions {
+                            local_time: *local_time,
+                            utc_offset: *utc_offset,
+                            show_visit_count: *show_visit_count,
+                            show_codes: *show_codes,
+                            flags: *flags,
+                            show_coords: *show_coords,
+                            show_source: *show_source,
+                            combine_subdivisions: *combine_subdivisions,
+                            explain: *explain,
+                        };
+                        display_border_crossings(crossings, &listing, &options)
+                    }
+                    OutputFormat::Kepler => {
+                        border_crossings_to_kepler(crossings, &listing, *coord_precision)
+                    }
+                    OutputFormat::Json => {
+                        border_crossings_to_json(crossings, &listing, *json_pretty)
+                    }
+                    OutputFormat::Jsonl => border_crossings_to_jsonl(crossings, &listing),
+                    OutputFormat::Owntracks => records_to_owntracks(records, *coord_precision),
+                    OutputFormat::Markdown => border_crossings_to_markdown(crossings, &listing),
+                    OutputFormat::Table => border_crossings_to_table(crossings, &listing),
+                    OutputFormat::Gpx => {
+                        records_to_gpx(records, *coord_precision, *interpolate_track)
+                    }
+                }
+            };
+            match output_dir.as_deref().filter(|_| *split_by_year) {
+                Some(dir) => {
+                    fs::create_dir_all(dir).map_err(|e| {
+                        error!("could not create directory {dir:?}: {e}");
+                        ExitCode::FAILURE
+                    })?;
+                    // a track is split by the years of its records, and a list by the years of its crossings
+                    let years: Vec<(i32, Vec<BorderCrossing>, Vec<Record>)> = if format.is_track() {
+                        partition_by_period(&records, Period::Year, |r| r.timestamp)
+                            .into_iter()
+                            .map(|(start, records)| (start.year(), vec![], records))
+                            .collect()
+                    } else {
+                        partition_by_period(&crossings, Period::Year, |c| c.timestamp)
+                            .into_iter()
+                            .map(|(start, crossings)| (start.year(), crossings, vec![]))
+                            .collect()
+                    };
+                    for (i, (year, crossings, records)) in years.iter().enumerate() {
+                        let following = years.get(i + 1).and_then(|(_, next, _)| next.first());
+                        let path = dir.join(format!("crossings-{year}.{}", format.extension()));
+                        let s = render(crossings, following, records);
+                        write_output(&s, &Some(path), cli.compress);
+                    }
+                    info!("wrote {} files to {dir:?}", years.len());
+                }
+                None => write_output(
+                    &render(&crossings, None, &records),
+                    &cli.output,
+                    cli.compress,
+                ),
+            }
+            timer.lap("formatting");
+            timer.report();
+
+            // the state is only advanced once the output is written, so that a failed run is retried in full
+            if let (Some(path), Some(latest)) = (state_file, latest_timestamp) {
+                write_last_timestamp(path, cutoff.map_or(latest, |c| c.max(latest)));
+            }
+        }
+        Some(Commands::Stats {
+            input: InputArgs { path },
+        }) => {
+            let records: Vec<Record> = read_usable_records(path, cli.keep_ocean)?;
+            let crossings = records_to_border_crossings(&records);
+            let s = Stats::new(&records, &crossings).to_string();
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::CountriesNotVisited {
+            input: InputArgs { path },
+            continent,
+        }) => {
+            let records: Vec<Record> = read_usable_records(path, cli.keep_ocean)?;
+            let s = countries_not_visited(&records, *continent)
+                .iter()
+                .map(localized_country_name)
+                .collect::<Vec<&str>>()
+                .join("\n");
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::Schengen {
+            input: InputArgs { path },
+        }) => {
+            let records: Vec<Record> = read_usable_records(path, cli.keep_ocean)?;
+            let Some(last_record) = records.last() else {
+                return Ok(());
+            };
+            let crossings = records_to_border_crossings(&records);
+            let occupancy = daily_occupancy(&crossings, last_record.timestamp);
+            let s = schengen_report(&occupancy)
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<String>>()
+                .join("\n");
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::Calendar {
+            input: InputArgs { path },
+        }) => {
+            let records: Vec<Record> = read_usable_records(path, cli.keep_ocean)?;
+            let Some(last_record) = records.last() else {
+                return Ok(());
+            };
+            let crossings = records_to_border_crossings(&records);
+            let s = render_calendar(&daily_primary_country(&crossings, last_record.timestamp));
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::Residency {
+            input: InputArgs { path },
+            csv,
+            day_boundary,
+            day_attribution,
+        }) => {
+            let records: Vec<Record> = read_usable_records(path, cli.keep_ocean)?;
+            let Some(last_record) = records.last() else {
+                return Ok(());
+            };
+            let crossings = records_to_border_crossings(&records);
+            let table = ResidencyTable::new(&attributed_days(
+                &crossings,
+                last_record.timestamp,
+                *day_boundary,
+                *day_attribution,
+            ));
+            let s = if *csv {
+                table.to_csv()
+            } else {
+                table.to_string()
+            };
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::Stays {
+            input: InputArgs { path },
+            radius,
+            min_dwell,
+        }) => {
+            let records: Vec<Record> = read_usable_records(path, cli.keep_ocean)?;
+            let s = find_stays(&records, *radius, *min_dwell)
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<String>();
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::SemanticVisits { path }) => {
+            let s = read_place_visits(path)
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<String>();
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::Segments {
+            input: InputArgs { path },
+        }) => {
+            let records: Vec<Record> = read_usable_records(path, cli.keep_ocean)?;
+            let crossings = records_to_border_crossings(&records);
+            let s = segments(&crossings)
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<String>>()
+                .join("\n");
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::Visits {
+            input: InputArgs { path },
+            first_entry_only,
+        }) => {
+            let records: Vec<Record> = read_usable_records(path, cli.keep_ocean)?;
+            let crossings = records_to_border_crossings(&records);
+            let s = if *first_entry_only {
+                first_entries(&crossings)
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            } else {
+                visits(&crossings)
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<String>()
+            };
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::TimeInRegion {
+            input: InputArgs { path },
+            top,
+            rollup: period,
+            format,
+            json_pretty,
+        }) => {
+            let records: Vec<Record> = read_usable_records(path, cli.keep_ocean)?;
+            let Some(last_record) = records.last() else {
+                return Ok(());
+            };
+            let crossings = records_to_border_crossings(&records);
+            let s = if let Some(period) = period {
+                rollup(&crossings, *period)
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            } else {
+                let mut summaries = summarize_visits(&visits(&crossings), last_record.timestamp);
+                if let Some(n) = top {
+                    summaries.truncate(*n);
+                }
+                match format {
+                    SummaryFormat::Text => summaries
+                        .iter()
+                        .map(|s| s.to_string())
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                    SummaryFormat::Json => summaries_to_json(&summaries, *json_pretty),
+                }
+            };
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::Diff { old, new }) => {
+            let old_crossings =
+                records_to_border_crossings(&read_usable_records(old, cli.keep_ocean)?);
+            let new_crossings =
+                records_to_border_crossings(&read_usable_records(new, cli.keep_ocean)?);
+            let crossings = crossings_since(&old_crossings, &new_crossings);
+            if crossings.is_empty() {
+                warn!("No new border crossings found");
+            }
+            let s = display_border_crossings(
+                &crossings,
+                &Listing::default(),
+                &DisplayOptions::default(),
+            );
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::TimezoneChanges {
+            input: InputArgs { path },
+        }) => {
+            let records: Vec<Record> = read_usable_records(path, cli.keep_ocean)?;
+            let s = offset_changes(&records)
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<String>>()
+                .join("\n");
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::Cache {
+            input: InputArgs { path },
+        }) => {
+            // the cache keeps every record, so that reading it with --keep-ocean finds the same records as the export
+            let records: Vec<Record> = read_usable_records(path, true)?;
+            if cli
+                .output
+                .as_ref()
+                .is_some_and(|o| !o.to_string_lossy().ends_with(CACHE_SUFFIX))
+            {
+                warn!("the cache will only be recognized as input if its file name ends in {CACHE_SUFFIX}");
+            }
+            write_output(&records_to_cache(&records), &cli.output, cli.compress);
+        }
+        Some(Commands::Completions { shell }) => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            let mut script: Vec<u8> = vec![];
+            generate(*shell, &mut command, name, &mut script);
+            let s = String::from_utf8(script)
+                .unwrap_or_else(|e| panic!("could not read completion script as utf-8: {e}"));
+            // the script already ends with a newline, which write_output would otherwise repeat
+            write_output(s.trim_end(), &cli.output, cli.compress);
+        }
+        Some(Commands::Doctor { path }) => {
+            // a directory may cover several archives, each of which gets its own report
+            let s = expand_paths(std::slice::from_ref(path))
+                .iter()
+                .map(|path| {
+                    let format = detect_format(path);
+                    let (mut records, stats) = read_records_from_file(path);
+                    records.sort_by(Record::cmp_chronological);
+                    HealthReport::new(format, &records, stats).to_string()
+                })
+                .collect::<Vec<String>>()
+                .join("\n\n");
+            write_output(&s, &cli.output, cli.compress);
+        }
+        Some(Commands::Geocode {
+            latitude,
+            longitude,
+        }) => {
+            let regions = regions_at(*latitude, *longitude).unwrap_or_else(|e| {
+                panic!("could not look up regions for ({latitude}, {longitude}): {e}")
+            });
+            let s = if regions.is_empty() {
+                "No regions found".to_string()
+            } else {
+                regions
+                    .iter()
+                    .map(|r| format!("{:<8}{r}", r.code().unwrap_or_default()))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            };
+            write_output(&s, &cli.output, cli.compress);
         }
         None => {}
     }
+    Ok(())
 }
 
-/// compares each element in v to its predecessor using the given predicate
-/// predicate is (current, previous) -> bool
-/// if the predicate returns true, the element is placed in the returned Vec
-/// if the predicate returns false, the element will not be contained in the returned Vec
-/// the first element in v is always included, because there is no previous element to compare to
-fn compare_and_retain<T: Clone>(v: &Vec<T>, predicate: fn(&T, &T) -> bool) -> Vec<T> {
-    let mut new_vec: Vec<&T> = vec![];
-    for i in 0..v.len() {
-        let element = v.get(i).unwrap();
-        let previous_element = v.get(i - 1);
-        match previous_element {
-            Some(prev) => {
-                if predicate(element, prev) {
-                    new_vec.push(element)
-                }
-            }
-            // if there is no previous element, it is by default included
-            None => new_vec.push(element),
+/// reads and merges the records from each of the given files, failing if any file had no usable records
+/// records in the open ocean are excluded unless keep_ocean is set
+fn read_usable_records(paths: &[PathBuf], keep_ocean: bool) -> Result<Vec<Record>, ExitCode> {
+    let paths = &expand_paths(paths);
+    let (mut records, stats) = read_records_with_stats(paths, &HashSet::new());
+    check_usable(paths, &stats)?;
+    if !keep_ocean {
+        drop_ocean_records(&mut records);
+    }
+    Ok(records)
+}
+
+/// excludes the records in the open ocean, outside of any region
+/// these are often artifacts such as null island, and would otherwise count as time in no region or break up a stay
+fn drop_ocean_records(records: &mut Vec<Record>) {
+    let record_count = records.len();
+    records.retain(|r| !r.regions().is_empty());
+    info!(
+        "excluded {} records in the open ocean",
+        record_count - records.len()
+    );
+}
+
+/// fails if any of the files contained records but none that were usable
+/// an empty document is not a failure, since there was nothing to use
+fn check_usable(paths: &[PathBuf], stats: &[ParseStats]) -> Result<(), ExitCode> {
+    match paths
+        .iter()
+        .zip(stats)
+        .find(|(_, s)| s.total > 0 && s.kept == 0)
+    {
+        Some((path, s)) => {
+            error!("{path:?} has no usable records: {s}");
+            Err(ExitCode::from(EXIT_NO_USABLE_RECORDS))
         }
+        None => Ok(()),
     }
+}
 
-    new_vec.iter().map(|&item| item.clone()).collect()
-}
-
-fn read_records_from_file(path: &PathBuf) -> Vec<Record> {
-    // extract json string from Records.json
-    let json_str = if path.extension() == Some(OsStr::new("zip")) {
-        // if .zip
-        // extract the data of Records.json from within the zip as a &str
-        let file = fs::File::open(path).unwrap_or_else(|e| panic!("could not open {path:?}: {e}"));
-        let bufreader = std::io::BufReader::new(file);
-        let mut archive = ZipArchive::new(bufreader).unwrap();
-        // find Records.json within the zip archive
-        let mut records_file = archive
-            .by_name("Takeout/Location History (Timeline)/Records.json")
-            .unwrap_or_else(|e| panic!("could not extract data from Records.json: {e}"));
-        let mut buf: Vec<u8> = Vec::new();
-        records_file
-            .read_to_end(&mut buf)
-            .unwrap_or_else(|e| panic!("could not read Records.json: {e}"));
-        std::str::from_utf8(&buf)
-            .unwrap_or_else(|e| {
-                panic!("could not read data from Records.json as utf-8 string: {e}")
-            })
-            .to_string()
-    } else if path.extension() == Some(OsStr::new("json")) {
-        // if .json
-        // read file to string
-        std::fs::read_to_string(path)
-            .unwrap_or_else(|e| panic!("could not read file {path:?}: {e}"))
-    } else {
-        let ext = path.extension().unwrap().to_str().unwrap();
-        panic!("could not handle unknown filetype, must be one of {{.zip, .json}}: {ext}");
+/// sends logs to stderr, keeping stdout for the command's output
+/// warnings are logged by default; each -v raises the level by one, and -q lowers it to errors only
+fn init_logging(verbose: u8, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Warn,
+        (false, 1) => LevelFilter::Info,
+        (false, 2) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
     };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .format_timestamp(None)
+        .init();
+}
+
+/// parses a command line argument as a duration, given as a whole number followed by a unit of s, m, h, d, or w, such as 90m or 7d
+fn parse_duration(duration: &str) -> Result<TimeDelta, String> {
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+    let (amount, unit) = duration.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| {
+        format!("unrecognized duration {duration}: expected a number followed by a unit")
+    })?;
+    match unit {
+        "s" => Ok(TimeDelta::seconds(amount)),
+        "m" => Ok(TimeDelta::minutes(amount)),
+        "h" => Ok(TimeDelta::hours(amount)),
+        "d" => Ok(TimeDelta::days(amount)),
+        "w" => Ok(TimeDelta::weeks(amount)),
+        _ => Err(format!(
+            "unrecognized duration {duration}: the unit must be one of s, m, h, d, or w"
+        )),
+    }
+}
 
-    // deserialize the document to rust struct
-    let document: JsonDocument = serde_json::from_str(&json_str)
-        .unwrap_or_else(|e| panic!("could not deserialize json: {e}"));
+/// parses a command line argument as a fixed offset from UTC, such as +09:00 or -05:30
+fn parse_utc_offset(offset: &str) -> Result<FixedOffset, String> {
+    offset
+        .parse::<FixedOffset>()
+        .map_err(|e| format!("unrecognized utc offset {offset}: {e}"))
+}
 
-    // convert to Vec<Record>
-    document
-        .locations
-        .iter()
-        .flat_map(|r| Record::from_json(r))
-        .collect()
+/// parses a command line argument as a confidence, from 0 to 1
+fn parse_confidence(confidence: &str) -> Result<f64, String> {
+    match confidence.parse::<f64>() {
+        Ok(c) if (0.0..=1.0).contains(&c) => Ok(c),
+        _ => Err(format!(
+            "unrecognized confidence {confidence}, expected a number from 0 to 1"
+        )),
+    }
 }
 
-fn border_crossing_to_string(
-    crossing: &BorderCrossing,
-    next_crossing: &Option<&BorderCrossing>,
-) -> String {
-    let timestamp_str = crossing.timestamp.to_rfc2822();
-    let region_strings: String = crossing
-        .new_regions
-        .iter()
-        .map(|r| format!("    | {r}"))
-        .collect::<Vec<String>>()
-        .join("\n");
-    let duration_string = match next_crossing {
-        Some(next) => {
-            let days = (next.timestamp - crossing.timestamp).num_days();
-            format!("    | Duration: {days} Days")
-        }
-        None => "    | Duration Unknown".to_string(),
-    };
-    let complete_string = vec![
-        &timestamp_str,
-        "    |",
-        &region_strings,
-        &duration_string,
-        "    |\n",
-    ]
-    .join("\n");
-    complete_string
-}
-
-fn display_border_crossings(crossings: &Vec<BorderCrossing>) -> String {
-    let mut string: String = "".to_string();
-    for i in 0..crossings.len() {
-        let crossing = crossings.get(i).unwrap();
-        let maybe_next = crossings.get(i + 1);
-        string += &border_crossing_to_string(crossing, &maybe_next);
+/// parses a command line argument as a time of day, given as HH:MM such as 04:00
+fn parse_time_of_day(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|e| format!("unrecognized time of day {time}, expected HH:MM: {e}"))
+}
+
+/// parses a command line argument as an ISO 3166 alpha-2 country code
+fn parse_country_code(code: &str) -> Result<CountryCode, String> {
+    rust_iso3166::from_alpha2(&code.to_uppercase())
+        .ok_or_else(|| format!("unrecognized country code: {code}"))
+}
+
+/// parses a command line argument as an ISO 3166 region code
+fn parse_region_code(code: &str) -> Result<Region, String> {
+    match Region::from_code(&code.to_uppercase()) {
+        Region::UnknownCode(c) => Err(format!("unrecognized region code: {c}")),
+        region => Ok(region),
     }
+}
 
-    string
-}
-
-/// requires records to be sorted by timestamp
-fn records_to_border_crossings(records: &Vec<Record>) -> Vec<BorderCrossing> {
-    // create a vector to track border crossings
-    let mut crossings: Vec<BorderCrossing> = vec![];
-    let mut maybe_prev: Option<Record> = None;
-    for record in records.iter() {
-        if let Some(prev) = maybe_prev {
-            // if we have a previous record, check before adding a new crossing
-            // check if we have a data gap of more than one day
-            let interval = record.timestamp - prev.timestamp;
-            if interval >= TimeDelta::days(1) {
-                // if we have a gap of more than one day, add a missing data border crossing
-                crossings.push(BorderCrossing {
-                    timestamp: prev.timestamp + TimeDelta::days(1), // timestamp is +1 day from previous record
-                    new_regions: vec![Region::MissingData].into_iter().collect(),
-                })
-            }
+/// parses a command line argument as an ISO 3166-2 subdivision code
+fn parse_subdivision_code(code: &str) -> Result<Region, String> {
+    rust_iso3166::iso3166_2::from_code(&code.to_uppercase())
+        .map(Region::Subdivision)
+        .ok_or_else(|| format!("unrecognized subdivision code: {code}"))
+}
 
-            // add crossing if we've changed locations (or if the last crossing was MissingData)
-            let location_diff = &record.regions() - &prev.regions();
-            if location_diff.len() > 0
-                || crossings
-                    .last()
-                    .is_some_and(|c| c.new_regions.contains(&Region::MissingData))
-            {
-                crossings.push(BorderCrossing::from(record))
-            }
-        } else {
-            // if there is no previous record, we unconditionally make a border crossing
-            crossings.push(BorderCrossing::from(record))
+/// measures the wall-clock time of each stage of a pipeline, if enabled
+struct StageTimer {
+    enabled: bool,
+    last: Instant,
+    stages: Vec<(&'static str, Duration)>,
+}
+
+impl StageTimer {
+    fn new(enabled: bool) -> Self {
+        StageTimer {
+            enabled,
+            last: Instant::now(),
+            stages: vec![],
+        }
+    }
+
+    /// records the time since the previous stage ended as the time taken by the named stage
+    fn lap(&mut self, stage: &'static str) {
+        if self.enabled {
+            let now = Instant::now();
+            self.stages.push((stage, now - self.last));
+            self.last = now;
         }
+    }
 
-        // update previous record
-        maybe_prev = Some(*record);
+    /// prints the time taken by each stage, and in total, to stderr
+    fn report(&self) {
+        if !self.enabled {
+            return;
+        }
+        for (stage, duration) in &self.stages {
+            eprintln!("{stage:<12}{:>10.3}s", duration.as_secs_f64());
+        }
+        let total: Duration = self.stages.iter().map(|(_, d)| *d).sum();
+        eprintln!("{:<12}{:>10.3}s", "total", total.as_secs_f64());
+    }
+}
+
+/// writes the trace lines to the given file, or to stderr if the path is "-"
+fn write_trace(lines: &[String], path: &PathBuf) {
+    let contents = lines.join("\n");
+    if path.as_os_str() == "-" {
+        eprintln!("{contents}");
+    } else {
+        fs::write(path, contents + "\n")
+            .unwrap_or_else(|e| panic!("could not write trace to {path:?}: {e}"));
     }
-    crossings
 }
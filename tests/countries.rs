@@ -0,0 +1,65 @@
+// tests for the listing of countries that the records never visit
+
+use chrono::{DateTime, Utc};
+use rust_iso3166::CountryCode;
+
+use takeout_travel_history::core::countries::{countries_not_visited, Continent};
+use takeout_travel_history::core::data::{Record, Source};
+use takeout_travel_history::core::input::read_records_from_files;
+use takeout_travel_history::core::names::collation_key;
+
+fn record((latitude, longitude): (f64, f64), timestamp: &str) -> Record {
+    Record {
+        latitude,
+        longitude,
+        timestamp: timestamp.parse::<DateTime<Utc>>().unwrap(),
+        source: Source::GPS,
+        accuracy: None,
+    }
+}
+
+fn visits_to_paris_and_berlin() -> Vec<Record> {
+    vec![
+        record((48.8566, 2.3522), "2023-09-10T08:00:00Z"),
+        record((52.52, 13.405), "2023-09-14T19:00:00Z"),
+    ]
+}
+
+fn alpha2s(countries: &[CountryCode]) -> Vec<&str> {
+    countries.iter().map(|c| c.alpha2).collect()
+}
+
+#[test]
+fn visited_countries_are_excluded_and_the_rest_are_sorted_by_name() {
+    let not_visited = countries_not_visited(&visits_to_paris_and_berlin(), None);
+    let codes = alpha2s(&not_visited);
+    assert!(!codes.contains(&"FR") && !codes.contains(&"DE"));
+    assert!(codes.contains(&"US") && codes.contains(&"ES"));
+    assert_eq!(not_visited.len(), rust_iso3166::ALL.len() - 2);
+    assert!(not_visited
+        .windows(2)
+        .all(|w| collation_key(w[0].name) <= collation_key(w[1].name)));
+}
+
+#[test]
+fn continent_limits_the_countries_to_those_on_it() {
+    let not_visited = countries_not_visited(&visits_to_paris_and_berlin(), Some(Continent::Europe));
+    let codes = alpha2s(&not_visited);
+    assert!(!codes.contains(&"FR") && !codes.contains(&"DE"));
+    assert!(codes.contains(&"ES") && !codes.contains(&"US"));
+    assert!(not_visited
+        .iter()
+        .all(|c| Continent::from_alpha2(c.alpha2) == Some(Continent::Europe)));
+    assert!(not_visited
+        .windows(2)
+        .all(|w| collation_key(w[0].name) <= collation_key(w[1].name)));
+}
+
+#[test]
+fn countries_visited_only_through_a_subdivision_are_visited() {
+    let fixture = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/subdivision_only.cache.json");
+    let not_visited = countries_not_visited(&read_records_from_files(&[fixture]), None);
+    assert!(!alpha2s(&not_visited).contains(&"US"));
+    assert_eq!(not_visited.len(), rust_iso3166::ALL.len() - 1);
+}
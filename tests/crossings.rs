@@ -0,0 +1,457 @@
+// tests for the per-record decision made by border crossing detection
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use takeout_travel_history::core::crossings::{
+    border_zone, collapse_consecutive_duplicates, detect_border_crossings, drop_shallow_crossings,
+    drop_subregion_crossings, primary_region, remap_obsolete_regions, step, DetectionOptions,
+};
+use takeout_travel_history::core::data::{
+    BorderCrossing, CrossingReason, Record, Region, Source, MISSING_DATA_THRESHOLD,
+};
+use takeout_travel_history::core::input::read_records_from_files;
+use takeout_travel_history::core::json::JsonActivityConfidence;
+use takeout_travel_history::core::semantic::{corroborate_crossings, Flight};
+
+const PARIS: (f64, f64) = (48.8566, 2.3522);
+const BERLIN: (f64, f64) = (52.52, 13.405);
+const NEW_YORK: (f64, f64) = (40.7128, -74.006);
+const NEWARK: (f64, f64) = (40.7357, -74.1724);
+
+fn record((latitude, longitude): (f64, f64), timestamp: &str) -> Record {
+    Record {
+        latitude,
+        longitude,
+        timestamp: timestamp.parse::<DateTime<Utc>>().unwrap(),
+        source: Source::GPS,
+        accuracy: None,
+    }
+}
+
+/// steps with the default threshold and options
+fn step_default(prev: Option<&Record>, cur: &Record) -> Vec<BorderCrossing> {
+    step(
+        prev,
+        cur,
+        MISSING_DATA_THRESHOLD,
+        &DetectionOptions::default(),
+    )
+}
+
+#[test]
+fn first_record_is_a_crossing() {
+    let cur = record(PARIS, "2023-09-10T08:00:00Z");
+    let crossings = step_default(None, &cur);
+    assert_eq!(crossings.len(), 1);
+    assert_eq!(crossings[0].timestamp, cur.timestamp);
+    assert_eq!(crossings[0].new_regions, cur.regions());
+}
+
+#[test]
+fn staying_in_the_same_regions_is_not_a_crossing() {
+    let prev = record(PARIS, "2023-09-10T08:00:00Z");
+    let cur = record(PARIS, "2023-09-10T20:00:00Z");
+    assert!(step_default(Some(&prev), &cur).is_empty());
+}
+
+#[test]
+fn plain_move_is_one_crossing() {
+    let prev = record(PARIS, "2023-09-10T08:00:00Z");
+    let cur = record(BERLIN, "2023-09-10T20:00:00Z");
+    let crossings = step_default(Some(&prev), &cur);
+    assert_eq!(crossings.len(), 1);
+    assert_eq!(crossings[0].timestamp, cur.timestamp);
+    assert_eq!(crossings[0].new_regions, cur.regions());
+}
+
+#[test]
+fn gap_then_same_region_is_missing_data_then_a_crossing() {
+    let prev = record(PARIS, "2023-09-10T08:00:00Z");
+    let cur = record(PARIS, "2023-09-14T09:00:00Z");
+    let crossings = step_default(Some(&prev), &cur);
+    assert_eq!(crossings.len(), 2);
+    assert_eq!(
+        crossings[0].new_regions,
+        [Region::MissingData].into_iter().collect()
+    );
+    assert_eq!(
+        crossings[0].timestamp,
+        prev.timestamp + MISSING_DATA_THRESHOLD
+    );
+    assert_eq!(crossings[1].new_regions, cur.regions());
+    assert_eq!(
+        [crossings[0].reason, crossings[1].reason],
+        [CrossingReason::Gap, CrossingReason::PostGap]
+    );
+}
+
+#[test]
+fn missing_data_is_stamped_one_gap_threshold_after_the_last_record() {
+    let prev = record(PARIS, "2023-09-10T08:00:00Z");
+    let cur = record(BERLIN, "2023-09-14T09:00:00Z");
+    let crossings = step_default(Some(&prev), &cur);
+    assert_eq!(
+        crossings[0].timestamp,
+        "2023-09-11T08:00:00Z".parse::<DateTime<Utc>>().unwrap()
+    );
+    assert_eq!(crossings[1].timestamp, cur.timestamp);
+
+    // a custom gap threshold moves the stamp along with it
+    let options = DetectionOptions::default();
+    let crossings = step(Some(&prev), &cur, TimeDelta::hours(12), &options);
+    assert_eq!(
+        crossings[0].timestamp,
+        "2023-09-10T20:00:00Z".parse::<DateTime<Utc>>().unwrap()
+    );
+}
+
+#[test]
+fn gap_then_same_region_is_no_crossing_when_interpolating() {
+    let prev = record(PARIS, "2023-09-10T08:00:00Z");
+    let cur = record(PARIS, "2023-09-14T09:00:00Z");
+    let options = DetectionOptions {
+        interpolate_missing: true,
+        ..Default::default()
+    };
+    assert!(step(Some(&prev), &cur, MISSING_DATA_THRESHOLD, &options).is_empty());
+}
+
+#[test]
+fn gap_then_different_region_is_missing_data_then_a_crossing() {
+    let prev = record(PARIS, "2023-09-10T08:00:00Z");
+    let cur = record(BERLIN, "2023-09-14T09:00:00Z");
+    let crossings = step_default(Some(&prev), &cur);
+    assert_eq!(crossings.len(), 2);
+    assert_eq!(
+        crossings[0].new_regions,
+        [Region::MissingData].into_iter().collect()
+    );
+    assert_eq!(crossings[1].new_regions, cur.regions());
+}
+
+#[test]
+fn gap_shorter_than_the_threshold_is_not_missing_data() {
+    let prev = record(PARIS, "2023-09-10T08:00:00Z");
+    let cur = record(BERLIN, "2023-09-10T20:00:00Z");
+    let crossings = step(
+        Some(&prev),
+        &cur,
+        TimeDelta::hours(13),
+        &DetectionOptions::default(),
+    );
+    assert_eq!(crossings.len(), 1);
+    assert_eq!(crossings[0].new_regions, cur.regions());
+}
+
+#[test]
+fn moving_between_subdivisions_is_a_crossing() {
+    let prev = record(NEW_YORK, "2023-03-01T09:00:00Z");
+    let cur = record(NEWARK, "2023-03-01T15:00:00Z");
+    assert_eq!(step_default(Some(&prev), &cur).len(), 1);
+}
+
+#[test]
+fn moving_between_subdivisions_is_not_a_crossing_when_countries_only() {
+    let prev = record(NEW_YORK, "2023-03-01T09:00:00Z");
+    let cur = record(NEWARK, "2023-03-01T15:00:00Z");
+    let options = DetectionOptions {
+        countries_only: true,
+        ..Default::default()
+    };
+    assert!(step(Some(&prev), &cur, MISSING_DATA_THRESHOLD, &options).is_empty());
+}
+
+#[test]
+fn countries_only_crossings_contain_no_subdivisions() {
+    let prev = record(BERLIN, "2023-03-01T09:00:00Z");
+    let cur = record(NEWARK, "2023-03-01T15:00:00Z");
+    let options = DetectionOptions {
+        countries_only: true,
+        ..Default::default()
+    };
+    for crossing in [None, Some(&prev)]
+        .into_iter()
+        .flat_map(|p| step(p, &cur, MISSING_DATA_THRESHOLD, &options))
+    {
+        assert!(!crossing.new_regions.iter().any(|r| r.is_subregion()));
+    }
+}
+
+#[test]
+fn obsolete_regions_are_remapped_to_a_unique_successor() {
+    let mut crossings = vec![BorderCrossing {
+        timestamp: "1990-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+        new_regions: [Region::from_code("DDDE"), Region::from_code("CSHH")]
+            .into_iter()
+            .collect(),
+        coordinates: None,
+        reason: CrossingReason::RegionChange,
+        source: Source::GPS,
+        flight: None,
+    }];
+    remap_obsolete_regions(&mut crossings);
+    assert_eq!(
+        crossings[0].new_regions,
+        [Region::from_code("DE"), Region::from_code("CSHH")]
+            .into_iter()
+            .collect()
+    );
+}
+
+#[test]
+fn gap_longer_than_the_fill_cap_is_missing_data_when_interpolating() {
+    let prev = record(PARIS, "2023-09-10T08:00:00Z");
+    let cur = record(PARIS, "2023-09-14T09:00:00Z");
+    let capped = |days| DetectionOptions {
+        interpolate_missing: true,
+        max_gap_fill: Some(TimeDelta::days(days)),
+        ..Default::default()
+    };
+    assert_eq!(
+        step(Some(&prev), &cur, MISSING_DATA_THRESHOLD, &capped(2)).len(),
+        2
+    );
+    assert!(step(Some(&prev), &cur, MISSING_DATA_THRESHOLD, &capped(7)).is_empty());
+}
+
+#[test]
+fn leaving_land_is_a_crossing_into_international_waters_when_tracking_the_ocean() {
+    let prev = record(PARIS, "2023-09-10T08:00:00Z");
+    let cur = record((45.0, -30.0), "2023-09-10T20:00:00Z");
+    assert!(step_default(Some(&prev), &cur).is_empty());
+    let options = DetectionOptions {
+        track_ocean: true,
+        ..Default::default()
+    };
+    let crossings = step(Some(&prev), &cur, MISSING_DATA_THRESHOLD, &options);
+    assert_eq!(crossings.len(), 1);
+    assert_eq!(
+        crossings[0].new_regions,
+        [Region::InternationalWaters].into_iter().collect()
+    );
+}
+
+#[test]
+fn collapsed_subdivisions_are_reported_as_their_country() {
+    let prev = record(NEW_YORK, "2023-03-01T09:00:00Z");
+    let cur = record(NEWARK, "2023-03-01T15:00:00Z");
+    let options = DetectionOptions {
+        collapse_subdivisions: true,
+        ..Default::default()
+    };
+    assert!(step(Some(&prev), &cur, MISSING_DATA_THRESHOLD, &options).is_empty());
+    let crossings = step(None, &cur, MISSING_DATA_THRESHOLD, &options);
+    assert_eq!(
+        crossings[0].new_regions,
+        [Region::from_code("US")].into_iter().collect()
+    );
+}
+
+#[test]
+fn primary_region_prefers_the_lowest_country_code() {
+    let regions = |codes: &[&str]| codes.iter().map(|c| Region::from_code(c)).collect();
+    assert_eq!(
+        primary_region(&regions(&["FR", "CH", "CH-GE"])),
+        Some(Region::from_code("CH"))
+    );
+    assert_eq!(
+        primary_region(&regions(&["US-NY"])),
+        Some(Region::from_code("US"))
+    );
+    assert_eq!(
+        primary_region(&regions(&["CSHH"])),
+        Some(Region::from_code("CSHH"))
+    );
+    assert_eq!(primary_region(&regions(&[])), None);
+}
+
+#[test]
+fn antarctic_coordinates_are_in_antarctica() {
+    let options = DetectionOptions {
+        track_ocean: true,
+        ..Default::default()
+    };
+    for coordinates in [(-80.0, 0.0), (-75.0, -60.0), (-70.0, 100.0)] {
+        assert_eq!(
+            options.regions(&record(coordinates, "2023-01-10T08:00:00Z")),
+            [Region::from_code("AQ")].into_iter().collect()
+        );
+    }
+}
+
+#[test]
+fn consecutive_gaps_are_each_followed_by_the_record_after_them() {
+    let records = [
+        record(PARIS, "2023-09-01T23:00:00Z"),
+        record(PARIS, "2023-09-05T01:00:00Z"),
+        record(PARIS, "2023-09-12T23:30:00Z"),
+    ];
+    let crossings = detect_border_crossings(&records, &DetectionOptions::default(), None);
+    assert_eq!(
+        crossings.iter().map(|c| c.reason).collect::<Vec<_>>(),
+        [
+            CrossingReason::FirstRecord,
+            CrossingReason::Gap,
+            CrossingReason::PostGap,
+            CrossingReason::Gap,
+            CrossingReason::PostGap,
+        ]
+    );
+    // each marker is stamped one threshold after the record before its gap, whatever the time of day
+    assert_eq!(
+        crossings[1].timestamp,
+        records[0].timestamp + MISSING_DATA_THRESHOLD
+    );
+    assert_eq!(
+        crossings[3].timestamp,
+        records[1].timestamp + MISSING_DATA_THRESHOLD
+    );
+    for crossings in [
+        crossings.clone(),
+        collapse_consecutive_duplicates(&crossings),
+    ] {
+        assert_eq!(crossings.len(), 5);
+        assert!(!crossings.windows(2).any(|pair| pair
+            .iter()
+            .all(|c| c.new_regions.contains(&Region::MissingData))));
+    }
+}
+
+#[test]
+fn grazing_a_border_is_not_a_crossing_with_a_buffer() {
+    let records = [
+        record(NEW_YORK, "2023-03-01T09:00:00Z"),
+        record(NEWARK, "2023-03-01T10:00:00Z"),
+        record(NEW_YORK, "2023-03-01T11:00:00Z"),
+        record((40.8176, -73.9482), "2023-03-01T12:00:00Z"),
+        record(BERLIN, "2023-03-01T20:00:00Z"),
+        record((52.4, 13.3), "2023-03-01T21:00:00Z"),
+    ];
+    let crossings = detect_border_crossings(&records, &DetectionOptions::default(), None);
+    assert_eq!(crossings.len(), 4);
+    // Newark is left without traveling within it, while New York and Berlin are each traveled over 5 km into
+    let buffered =
+        collapse_consecutive_duplicates(&drop_shallow_crossings(&crossings, &records, 5.0));
+    assert_eq!(
+        buffered.iter().map(|c| c.timestamp).collect::<Vec<_>>(),
+        [records[0].timestamp, records[4].timestamp]
+    );
+}
+
+#[test]
+fn a_crossing_corroborated_by_a_flight_survives_the_buffer() {
+    let records = [
+        record(PARIS, "2023-03-01T09:00:00Z"),
+        record(BERLIN, "2023-03-01T12:00:00Z"),
+        record(PARIS, "2023-03-01T20:00:00Z"),
+    ];
+    let crossings = detect_border_crossings(&records, &DetectionOptions::default(), None);
+    assert_eq!(crossings.len(), 3);
+    let berlin = records[1].timestamp;
+
+    // a single record in Berlin has no path into it, so it is dropped without corroboration
+    let buffered =
+        collapse_consecutive_duplicates(&drop_shallow_crossings(&crossings, &records, 5.0));
+    assert!(buffered.iter().all(|c| c.timestamp != berlin));
+
+    let flights = [Flight {
+        departure: "2023-03-01T10:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+        arrival: "2023-03-01T11:30:00Z".parse::<DateTime<Utc>>().unwrap(),
+        confidence: Some(JsonActivityConfidence::High),
+    }];
+    let mut corroborated = crossings.clone();
+    assert_eq!(corroborate_crossings(&mut corroborated, &flights), 1);
+    let buffered =
+        collapse_consecutive_duplicates(&drop_shallow_crossings(&corroborated, &records, 5.0));
+    let kept = buffered.iter().find(|c| c.timestamp == berlin).unwrap();
+    assert!(kept.flight.is_some());
+}
+
+#[test]
+fn points_in_several_countries_are_a_border_zone_that_does_not_resolve_a_crossing() {
+    let options = DetectionOptions {
+        border_zones: true,
+        ..Default::default()
+    };
+    let records = [
+        record((46.6, 32.6), "2023-03-01T09:00:00Z"),
+        record((44.9, 34.1), "2023-03-01T15:00:00Z"),
+        record((55.75, 37.6), "2023-03-02T08:00:00Z"),
+    ];
+    assert_eq!(
+        options.regions(&records[1]).first().unwrap().to_string(),
+        "Border Zone: Russian Federation/Ukraine"
+    );
+    let mut trace: Vec<String> = vec![];
+    let crossings = detect_border_crossings(&records, &options, Some(&mut trace));
+    assert_eq!(
+        crossings.iter().map(|c| c.timestamp).collect::<Vec<_>>(),
+        [records[0].timestamp, records[2].timestamp]
+    );
+    assert!(trace.contains(&"    no crossing: within border zone [RU/UA]".to_string()));
+    // a territory and the country it belongs to are not ambiguous
+    let puerto_rico = record((18.2, -66.5), "2023-03-01T09:00:00Z");
+    assert_eq!(border_zone(&puerto_rico.regions()), None);
+}
+
+#[test]
+fn great_circle_path_spans_the_gap_in_evenly_spaced_steps() {
+    let (paris, berlin) = (
+        record(PARIS, "2023-09-10T08:00:00Z"),
+        record(BERLIN, "2023-09-14T08:00:00Z"),
+    );
+    let path = paris.great_circle_path(&berlin, 100.0);
+    assert_eq!(path.first(), Some(&PARIS));
+    let (latitude, longitude) = *path.last().unwrap();
+    assert!((latitude - BERLIN.0).abs() < 1e-9 && (longitude - BERLIN.1).abs() < 1e-9);
+    let points: Vec<Record> = path
+        .iter()
+        .map(|p| record(*p, "2023-09-10T08:00:00Z"))
+        .collect();
+    let length: f64 = points.windows(2).map(|w| w[0].distance_km(&w[1])).sum();
+    assert!(points.windows(2).all(|w| w[0].distance_km(&w[1]) <= 100.0));
+    assert!((length - paris.distance_km(&berlin)).abs() < 1e-6);
+}
+
+#[test]
+fn subdivision_only_regions_within_one_country_are_not_country_crossings() {
+    let fixture = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/subdivision_only.cache.json");
+    let records = read_records_from_files(&[fixture]);
+    assert!(records
+        .iter()
+        .all(|r| r.regions().iter().all(|region| region.is_subregion())));
+
+    // every record is in a different state, so each is a crossing at full granularity
+    let crossings = detect_border_crossings(&records, &DetectionOptions::default(), None);
+    assert_eq!(crossings.len(), records.len());
+    assert_eq!(drop_subregion_crossings(&crossings).len(), 1);
+
+    let options = DetectionOptions {
+        countries_only: true,
+        ..Default::default()
+    };
+    let crossings = detect_border_crossings(&records, &options, None);
+    assert_eq!(crossings.len(), 1);
+    assert_eq!(
+        crossings[0].new_regions,
+        [Region::from_code("US")].into_iter().collect()
+    );
+}
+
+#[test]
+fn trace_records_the_gap_and_the_crossing_after_it() {
+    let fixture = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/missing_data_gap.json");
+    let records = read_records_from_files(&[fixture]);
+    let mut trace: Vec<String> = vec![];
+    detect_border_crossings(&records, &DetectionOptions::default(), Some(&mut trace));
+    let gap = trace.iter().position(|l| {
+        l == "    gap of 85 hours since previous record, inserted missing data at 2023-09-11T20:00:00+00:00"
+    });
+    let crossing = trace
+        .iter()
+        .position(|l| l.starts_with("    crossing: entered new regions [") && l.contains("DE"));
+    assert!(gap.is_some() && crossing.is_some());
+    assert!(gap < crossing);
+}
@@ -0,0 +1,115 @@
+// tests for the rendering of border crossings as text
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use takeout_travel_history::core::data::{BorderCrossing, CrossingReason, Region, Source};
+use takeout_travel_history::core::display::{
+    border_crossing_to_string, display_border_crossings, DisplayOptions, Listing, SortOrder,
+};
+use takeout_travel_history::core::output::border_crossings_to_json;
+
+fn crossing(code: &str, timestamp: &str) -> BorderCrossing {
+    BorderCrossing {
+        timestamp: timestamp.parse::<DateTime<Utc>>().unwrap(),
+        new_regions: [Region::from_code(code)].into_iter().collect(),
+        coordinates: None,
+        reason: CrossingReason::RegionChange,
+        source: Source::GPS,
+        flight: None,
+    }
+}
+
+#[test]
+fn descending_order_keeps_durations_until_the_chronologically_next_crossing() {
+    let crossings = [
+        crossing("FR", "2023-09-01T08:00:00Z"),
+        crossing("DE", "2023-09-04T08:00:00Z"),
+        crossing("PL", "2023-09-11T08:00:00Z"),
+    ];
+    let listing = Listing {
+        sort: SortOrder::Desc,
+        ..Default::default()
+    };
+    let options = DisplayOptions::default();
+    let block =
+        |i: usize| border_crossing_to_string(&crossings[i], &crossings.get(i + 1), None, &options);
+    let expected = [block(2), block(1), block(0)].concat();
+    let actual = display_border_crossings(&crossings, &listing, &options);
+    assert_eq!(actual, expected);
+    let durations: Vec<&str> = actual.lines().filter(|l| l.contains("Duration")).collect();
+    assert_eq!(
+        durations,
+        [
+            "    | Duration Unknown",
+            "    | Duration: 7 Days",
+            "    | Duration: 3 Days"
+        ]
+    );
+}
+
+#[test]
+fn text_and_json_list_the_same_crossings_when_sorted_and_limited() {
+    let crossings = [
+        crossing("FR", "2023-09-01T08:00:00Z"),
+        crossing("DE", "2023-09-04T08:00:00Z"),
+        crossing("PL", "2023-09-11T08:00:00Z"),
+        crossing("CZ", "2023-09-13T08:00:00Z"),
+    ];
+    let listing = Listing {
+        sort: SortOrder::Desc,
+        limit: Some(2),
+        ..Default::default()
+    };
+    let text = display_border_crossings(&crossings, &listing, &DisplayOptions::default());
+    let text_timestamps: Vec<&str> = text.lines().filter(|l| l.ends_with("+0000")).collect();
+    let text_durations: Vec<&str> = text.lines().filter(|l| l.contains("Duration")).collect();
+    assert_eq!(
+        text_timestamps,
+        [
+            "Wed, 13 Sep 2023 08:00:00 +0000",
+            "Mon, 11 Sep 2023 08:00:00 +0000"
+        ]
+    );
+    assert_eq!(
+        text_durations,
+        ["    | Duration Unknown", "    | Duration: 2 Days"]
+    );
+
+    let json: Value =
+        serde_json::from_str(&border_crossings_to_json(&crossings, &listing, false)).unwrap();
+    let json = json.as_array().unwrap();
+    let json_timestamps: Vec<&str> = json
+        .iter()
+        .map(|c| c["timestamp"].as_str().unwrap())
+        .collect();
+    let json_durations: Vec<Option<i64>> =
+        json.iter().map(|c| c["duration_days"].as_i64()).collect();
+    assert_eq!(
+        json_timestamps,
+        ["2023-09-13T08:00:00+00:00", "2023-09-11T08:00:00+00:00"]
+    );
+    assert_eq!(json_durations, [None, Some(2)]);
+}
+
+#[test]
+fn a_slice_keeps_the_duration_of_its_last_crossing_until_the_following_crossing() {
+    let crossings = [
+        crossing("FR", "2022-12-20T08:00:00Z"),
+        crossing("DE", "2022-12-28T08:00:00Z"),
+        crossing("PL", "2023-01-02T08:00:00Z"),
+    ];
+    let listing = Listing {
+        following: crossings.get(2),
+        ..Default::default()
+    };
+    let text = display_border_crossings(&crossings[..2], &listing, &DisplayOptions::default());
+    let durations: Vec<&str> = text.lines().filter(|l| l.contains("Duration")).collect();
+    assert_eq!(
+        durations,
+        ["    | Duration: 8 Days", "    | Duration: 5 Days"]
+    );
+    let json: Value =
+        serde_json::from_str(&border_crossings_to_json(&crossings[..2], &listing, false)).unwrap();
+    assert_eq!(json[1]["duration_days"], 5);
+}
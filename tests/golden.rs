@@ -0,0 +1,54 @@
+// golden-output tests for the border crossing pipeline
+// each fixture in tests/fixtures is a small synthetic Records.json, paired with the text output expected from it
+
+use std::fs;
+use std::path::PathBuf;
+
+use takeout_travel_history::core::crossings::records_to_border_crossings;
+use takeout_travel_history::core::display::{display_border_crossings, DisplayOptions, Listing};
+use takeout_travel_history::core::input::read_records_from_files;
+use takeout_travel_history::core::output::{records_to_gpx, DEFAULT_COORD_PRECISION};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+/// renders the fixture's border crossings with the default display options and compares them to its golden output
+fn assert_golden(name: &str) {
+    let records = read_records_from_files(&[fixture(&format!("{name}.json"))]);
+    let crossings = records_to_border_crossings(&records);
+    let actual =
+        display_border_crossings(&crossings, &Listing::default(), &DisplayOptions::default());
+    let expected = fs::read_to_string(fixture(&format!("{name}.txt")))
+        .unwrap_or_else(|e| panic!("could not read golden output for {name}: {e}"));
+    assert_eq!(
+        actual, expected,
+        "output of {name} differs from its golden output"
+    );
+}
+
+#[test]
+fn border_hop() {
+    assert_golden("border_hop");
+}
+
+#[test]
+fn missing_data_gap() {
+    assert_golden("missing_data_gap");
+}
+
+#[test]
+fn subdivision_change() {
+    assert_golden("subdivision_change");
+}
+
+#[test]
+fn missing_data_gap_gpx() {
+    let records = read_records_from_files(&[fixture("missing_data_gap.json")]);
+    let actual = records_to_gpx(&records, DEFAULT_COORD_PRECISION, false);
+    let expected = fs::read_to_string(fixture("missing_data_gap.gpx"))
+        .unwrap_or_else(|e| panic!("could not read golden output for missing_data_gap: {e}"));
+    assert_eq!(actual, expected.trim_end());
+}
@@ -0,0 +1,375 @@
+// tests for the passes applied to records after they are read
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use takeout_travel_history::core::cache::{records_from_cache, records_to_cache};
+use takeout_travel_history::core::data::{Record, RecordError, Source};
+use takeout_travel_history::core::input::{
+    expand_paths, read_records_from_file, read_records_with_stats, records_from_reader,
+    thin_records, InputFormat,
+};
+use takeout_travel_history::core::json::JsonRecord;
+use takeout_travel_history::core::semantic::{flight_at, read_flights, read_place_visits};
+use takeout_travel_history::core::state::records_since;
+use takeout_travel_history::core::stays::{redact_long_stays, DEFAULT_STAY_RADIUS_M};
+
+fn record(latitude: f64, longitude: f64, timestamp: &str) -> Record {
+    Record {
+        latitude,
+        longitude,
+        timestamp: timestamp.parse::<DateTime<Utc>>().unwrap(),
+        source: Source::GPS,
+        accuracy: None,
+    }
+}
+
+#[test]
+fn thinning_drops_stationary_fixes_within_the_window() {
+    let records = [
+        record(48.85660, 2.35220, "2023-09-10T08:00:00Z"),
+        record(48.85661, 2.35221, "2023-09-10T08:00:10Z"),
+        record(48.85660, 2.35222, "2023-09-10T08:00:20Z"),
+        record(48.85661, 2.35220, "2023-09-10T08:01:10Z"),
+    ];
+    let thinned = thin_records(&records, TimeDelta::minutes(1));
+    let timestamps: Vec<DateTime<Utc>> = thinned.iter().map(|r| r.timestamp).collect();
+    assert_eq!(timestamps, vec![records[0].timestamp, records[3].timestamp]);
+}
+
+#[test]
+fn thinning_keeps_movement_within_the_window() {
+    let records = [
+        record(48.8566, 2.3522, "2023-09-10T08:00:00Z"),
+        record(48.8576, 2.3522, "2023-09-10T08:00:10Z"),
+        record(48.8586, 2.3522, "2023-09-10T08:00:20Z"),
+    ];
+    assert_eq!(thin_records(&records, TimeDelta::minutes(1)).len(), 3);
+}
+
+#[test]
+fn parsing_rejects_coordinates_beyond_the_valid_range() {
+    let json = |latitude: i64, longitude: i64| -> JsonRecord {
+        serde_json::from_value(serde_json::json!({
+            "latitudeE7": latitude,
+            "longitudeE7": longitude,
+            "timestamp": "2023-09-10T08:00:00Z",
+        }))
+        .unwrap()
+    };
+    assert!(Record::from_json(&json(488_566_000, 23_522_000)).is_ok());
+    assert_eq!(
+        Record::from_json(&json(4_294_967_295, 23_522_000)).unwrap_err(),
+        RecordError::OutOfRangeCoordinates
+    );
+    assert_eq!(
+        Record::from_json(&json(488_566_000, -1_800_000_001)).unwrap_err(),
+        RecordError::OutOfRangeCoordinates
+    );
+}
+
+#[test]
+fn cached_records_round_trip_with_their_regions() {
+    let records = [
+        record(40.7128, -74.006, "2023-03-01T09:00:00Z"),
+        record(45.0, -30.0, "2023-03-01T15:00:00Z"),
+    ];
+    let cached = records_from_cache(&records_to_cache(&records));
+    assert_eq!(cached.len(), records.len());
+    for (original, cached) in records.iter().zip(&cached) {
+        assert_eq!(original.timestamp, cached.timestamp);
+        assert_eq!(original.source, cached.source);
+        assert_eq!(original.regions(), cached.regions());
+    }
+}
+
+#[test]
+fn parsing_assumes_utc_for_timestamps_without_an_offset() {
+    let json: JsonRecord = serde_json::from_value(serde_json::json!({
+        "latitudeE7": 407_128_000,
+        "longitudeE7": -740_060_000,
+        "timestamp": "2023-09-11T08:00:00.500",
+    }))
+    .unwrap();
+    assert_eq!(
+        Record::from_json(&json).unwrap().timestamp,
+        "2023-09-11T08:00:00.500Z".parse::<DateTime<Utc>>().unwrap()
+    );
+}
+
+/// writes a zip archive holding a single entry
+fn write_zip(path: &Path, entry_name: &str) {
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    zip.start_file(entry_name, zip::write::FileOptions::default())
+        .unwrap();
+    zip.write_all(b"{\"locations\": []}").unwrap();
+    zip.finish().unwrap();
+}
+
+#[test]
+fn split_exports_are_expanded_to_the_parts_containing_records_json() {
+    let dir = std::env::temp_dir().join(format!("split-export-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    write_zip(&dir.join("takeout-001.zip"), "Takeout/Drive/notes.txt");
+    write_zip(
+        &dir.join("takeout-002.zip"),
+        "Takeout/Location History/Records.json",
+    );
+    std::fs::write(dir.join("readme.txt"), "not an archive").unwrap();
+
+    let expected = vec![dir.join("takeout-002.zip")];
+    assert_eq!(expand_paths(std::slice::from_ref(&dir)), expected);
+    assert_eq!(expand_paths(&[dir.join("takeout-*.zip")]), expected);
+    assert_eq!(expand_paths(&[dir.join("takeout-00?.zip")]), expected);
+    let file = PathBuf::from("Records.json");
+    assert_eq!(expand_paths(std::slice::from_ref(&file)), vec![file]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn extracted_exports_are_expanded_to_their_records_json() {
+    let dir = std::env::temp_dir().join(format!("extracted-export-{}", std::process::id()));
+    let records_dir = dir.join("Takeout").join("Standortverlauf");
+    std::fs::create_dir_all(&records_dir).unwrap();
+    std::fs::copy(
+        "tests/fixtures/border_hop.json",
+        records_dir.join("Records.json"),
+    )
+    .unwrap();
+    write_zip(
+        &dir.join("takeout-001.zip"),
+        "Takeout/Location History/Records.json",
+    );
+
+    let expected = vec![records_dir.join("Records.json")];
+    assert_eq!(expand_paths(std::slice::from_ref(&dir)), expected);
+    let (records, _) = read_records_from_file(&expected[0]);
+    assert!(!records.is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn truncated_documents_keep_the_records_before_the_truncation() {
+    let document = std::fs::read_to_string("tests/fixtures/missing_data_gap.json").unwrap();
+    let path = std::env::temp_dir().join(format!("truncated-{}.json", std::process::id()));
+    // cut the document off partway through its final record
+    std::fs::write(&path, &document[..document.rfind("\"timestamp\"").unwrap()]).unwrap();
+
+    let (records, stats) = read_records_from_file(&path);
+    assert!(stats.truncated);
+    assert_eq!(stats.total, 3);
+    assert_eq!(records.len(), 3);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn excluded_sources_do_not_displace_other_sources_at_the_same_instant() {
+    let path = std::env::temp_dir().join(format!("same-instant-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{"locations": [
+            {"latitudeE7": 488566000, "longitudeE7": 23522000, "source": "GPS", "timestamp": "2023-09-10T08:00:00Z"},
+            {"latitudeE7": 488570000, "longitudeE7": 23530000, "source": "CELL", "timestamp": "2023-09-10T08:00:00Z"}
+        ]}"#,
+    )
+    .unwrap();
+
+    let paths = [path.clone()];
+    let (records, _) = read_records_with_stats(&paths, &HashSet::new());
+    let sources: Vec<Source> = records.iter().map(|r| r.source).collect();
+    assert_eq!(sources, vec![Source::GPS]);
+    let (records, _) = read_records_with_stats(&paths, &HashSet::from([Source::GPS]));
+    let sources: Vec<Source> = records.iter().map(|r| r.source).collect();
+    assert_eq!(sources, vec![Source::Cell]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn confidence_is_lower_when_the_accuracy_circle_straddles_a_border() {
+    let with_accuracy = |latitude, longitude, accuracy| Record {
+        accuracy: Some(accuracy),
+        ..record(latitude, longitude, "2023-09-10T08:00:00Z")
+    };
+    // the Rhine between Strasbourg and Kehl is the border between France and Germany
+    let near_border = with_accuracy(48.5734, 7.7990, 5000);
+    assert!(near_border.confidence() < 1.0);
+    assert_eq!(with_accuracy(48.8566, 2.3522, 50).confidence(), 1.0);
+    assert_eq!(
+        record(48.5734, 7.7990, "2023-09-10T08:00:00Z").confidence(),
+        1.0
+    );
+}
+
+#[test]
+fn semantic_place_visits_are_read_with_either_timestamp_format() {
+    let visits = read_place_visits(&[PathBuf::from("tests/fixtures/2023_MAY.json")]);
+    assert_eq!(visits.len(), 2);
+    assert_eq!(
+        visits[0].name.as_deref(),
+        Some("Coleman A. Young Municipal Center")
+    );
+    assert_eq!(visits[0].duration(), TimeDelta::minutes(270));
+    assert_eq!(visits[1].name, None);
+    assert_eq!(visits[1].duration(), TimeDelta::minutes(195));
+}
+
+#[test]
+fn redaction_blurs_only_the_long_stays() {
+    let mut records = vec![
+        record(48.856613, 2.352222, "2023-09-10T20:00:00Z"),
+        record(48.856701, 2.352301, "2023-09-11T02:00:00Z"),
+        record(48.856650, 2.352250, "2023-09-11T07:00:00Z"),
+        record(48.804865, 2.120355, "2023-09-11T08:00:00Z"),
+        record(48.804900, 2.120400, "2023-09-11T08:30:00Z"),
+    ];
+    let stays = redact_long_stays(&mut records, DEFAULT_STAY_RADIUS_M, TimeDelta::hours(8));
+    assert_eq!(stays.len(), 1);
+    for r in &records[..3] {
+        assert_eq!((r.latitude, r.longitude), (48.86, 2.35));
+    }
+    assert_eq!(
+        (records[3].latitude, records[3].longitude),
+        (48.804865, 2.120355)
+    );
+}
+
+#[test]
+fn only_confident_flights_are_read_and_coincide_with_the_crossings_after_landing() {
+    let flights = read_flights(&[PathBuf::from("tests/fixtures/2023_MAY.json")]);
+    assert_eq!(flights.len(), 1);
+    let at = |timestamp: &str| timestamp.parse::<DateTime<Utc>>().unwrap();
+    assert!(flight_at(&flights, at("2023-05-02T08:00:00Z")).is_some());
+    assert!(flight_at(&flights, at("2023-05-02T05:00:00Z")).is_none());
+    assert!(flight_at(&flights, at("2023-05-02T14:00:00Z")).is_none());
+}
+
+#[test]
+fn records_since_the_cutoff_keep_the_last_record_before_it() {
+    let records = vec![
+        record(48.8566, 2.3522, "2023-09-10T08:00:00Z"),
+        record(48.8566, 2.3522, "2023-09-10T09:00:00Z"),
+        record(52.52, 13.405, "2023-09-10T20:00:00Z"),
+    ];
+    let at = |timestamp: &str| timestamp.parse::<DateTime<Utc>>().unwrap();
+    let since = |cutoff: &str| {
+        records_since(&records, at(cutoff))
+            .iter()
+            .map(|r| r.timestamp)
+            .collect::<Vec<_>>()
+    };
+    let timestamps: Vec<_> = records.iter().map(|r| r.timestamp).collect();
+    assert_eq!(since("2023-09-10T09:00:00Z"), timestamps[1..]);
+    assert_eq!(since("2023-09-01T00:00:00Z"), timestamps);
+    assert_eq!(since("2023-09-11T00:00:00Z"), timestamps[2..]);
+}
+
+#[test]
+fn records_are_read_from_memory_in_each_format() {
+    let document = std::fs::read("tests/fixtures/border_hop.json").unwrap();
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+    zip.start_file(
+        "Takeout/Location History/Records.json",
+        zip::write::FileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(&document).unwrap();
+    let archive = zip.finish().unwrap().into_inner();
+
+    // an entry before Records.json is skipped, and a path too long for a plain tar header is stored in an extension
+    let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(
+        vec![],
+        flate2::Compression::default(),
+    ));
+    let mut append = |path: &str, data: &[u8]| {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        tar.append_data(&mut header, path, data).unwrap();
+    };
+    append("Takeout/archive_browser.html", b"<html></html>");
+    let long_folder = "Location History".repeat(8);
+    append(&format!("Takeout/{long_folder}/Records.json"), &document);
+    let tar_gz = tar.into_inner().unwrap().finish().unwrap();
+
+    let from_json = records_from_reader(document.as_slice(), InputFormat::Json).count();
+    let from_zip = records_from_reader(archive.as_slice(), InputFormat::Zip).count();
+    let from_tar_gz = records_from_reader(tar_gz.as_slice(), InputFormat::TarGz).count();
+    assert_eq!(from_json, 6);
+    assert_eq!(from_zip, from_json);
+    assert_eq!(from_tar_gz, from_json);
+}
+
+#[test]
+fn empty_files_are_reported_as_empty_rather_than_truncated() {
+    let path = std::env::temp_dir().join(format!("empty-{}.json", std::process::id()));
+    std::fs::write(&path, "").unwrap();
+
+    let (records, stats) = read_records_from_file(&path);
+    assert!(records.is_empty());
+    assert!(stats.empty);
+    assert!(!stats.truncated);
+    assert_eq!(
+        stats.to_string(),
+        "the file is empty, so there are no records to read"
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn records_are_yielded_before_the_rest_of_the_document_is_read() {
+    /// counts the bytes read through it
+    struct CountingReader<'a> {
+        data: &'a [u8],
+        read: std::rc::Rc<std::cell::Cell<usize>>,
+    }
+    impl std::io::Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.data.read(buf)?;
+            self.read.set(self.read.get() + n);
+            Ok(n)
+        }
+    }
+
+    // other keys before the locations array are skipped, and many records follow the first
+    let location = r#"{"latitudeE7":488566000,"longitudeE7":23522000,"source":"GPS","timestamp":"2023-09-10T08:00:00Z"}"#;
+    let document = format!(
+        r#"{{"exportedBy": {{"app": "Takeout"}}, "locations": [{}]}}"#,
+        vec![location; 10_000].join(",\n")
+    );
+    let read = std::rc::Rc::new(std::cell::Cell::new(0));
+    let reader = CountingReader {
+        data: document.as_bytes(),
+        read: read.clone(),
+    };
+    let mut records = records_from_reader(reader, InputFormat::Json);
+    assert!(records.next().is_some());
+    assert!(read.get() < document.len() / 10);
+    assert_eq!(records.count(), 9_999);
+}
+
+#[test]
+fn records_without_a_source_are_read_as_source_none() {
+    let json = |source: Option<&str>| -> JsonRecord {
+        let mut value = serde_json::json!({
+            "latitudeE7": 488_566_000,
+            "longitudeE7": 23_522_000,
+            "timestamp": "2023-09-10T08:00:00Z",
+        });
+        if let Some(source) = source {
+            value["source"] = source.into();
+        }
+        serde_json::from_value(value).unwrap()
+    };
+    assert_eq!(Record::from_json(&json(None)).unwrap().source, Source::None);
+    assert_eq!(
+        Record::from_json(&json(Some("UNKNOWN"))).unwrap().source,
+        Source::Unknown
+    );
+}
@@ -0,0 +1,37 @@
+// tests for the detection of nearby records classified into different regions
+
+use chrono::{DateTime, Utc};
+
+use takeout_travel_history::core::data::{Record, Region, Source};
+use takeout_travel_history::core::instability::{find_instabilities, DEFAULT_INSTABILITY_RADIUS_M};
+
+fn record(latitude: f64, longitude: f64, timestamp: &str) -> Record {
+    Record {
+        latitude,
+        longitude,
+        timestamp: timestamp.parse::<DateTime<Utc>>().unwrap(),
+        source: Source::GPS,
+        accuracy: None,
+    }
+}
+
+#[test]
+fn records_a_few_meters_apart_across_a_border_are_one_instability() {
+    // either side of the border between Montana and Alberta, which the boundary data draws just south of the 49th parallel
+    let records = vec![
+        record(48.998_95, -111.0, "2023-07-01T08:00:00Z"),
+        record(48.999_01, -111.0, "2023-07-01T08:01:00Z"),
+        record(48.999_01, -111.0, "2023-07-01T08:02:00Z"),
+    ];
+    let instabilities = find_instabilities(&records, DEFAULT_INSTABILITY_RADIUS_M);
+    assert_eq!(instabilities.len(), 1);
+    let instability = &instabilities[0];
+    assert!(instability.first_regions.contains(&Region::from_code("US")));
+    assert!(instability
+        .second_regions
+        .contains(&Region::from_code("CA")));
+    assert_eq!(instability.first.timestamp, records[0].timestamp);
+    assert_eq!(instability.second.timestamp, records[1].timestamp);
+    // 0.00006 degrees of latitude is about 6.7 meters
+    assert!((instability.distance_m() - 6.67).abs() < 0.05);
+}
@@ -0,0 +1,57 @@
+// tests for the display of regions in another language
+// the language can only be set once per process, so these tests are kept apart from those that display regions in English
+
+use std::collections::BTreeSet;
+use std::sync::Once;
+
+use takeout_travel_history::core::countries::{countries_not_visited, Continent};
+use takeout_travel_history::core::data::Region;
+use takeout_travel_history::core::names::{localized_country_name, set_language, Language};
+
+/// sets the language to French, once for all of the tests in this file
+fn french() {
+    static FRENCH: Once = Once::new();
+    FRENCH.call_once(|| set_language(Language::Fr));
+}
+
+#[test]
+fn regions_are_ordered_the_same_in_any_language() {
+    french();
+    let regions: BTreeSet<Region> = [
+        Region::MissingData,
+        Region::from_code("US-CA"),
+        Region::InternationalWaters,
+        Region::from_code("US"),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(
+        regions
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<String>>(),
+        [
+            "Eaux internationales",
+            "Données manquantes",
+            "États-Unis",
+            "California"
+        ]
+    );
+}
+
+#[test]
+fn countries_not_visited_are_sorted_by_their_localized_names() {
+    french();
+    let names: Vec<&str> = countries_not_visited(&[], Some(Continent::Africa))
+        .iter()
+        .map(localized_country_name)
+        .collect();
+    // Égypte is listed among the names starting with E, rather than after every unaccented name
+    let position = |name: &str| names.iter().position(|n| *n == name).unwrap();
+    assert!(position("Djibouti") < position("Égypte"));
+    assert!(position("Égypte") < position("Eritrea"));
+    assert!(position("Eritrea") < position("Gabon"));
+    // Afrique du Sud is South Africa in English, so it would be listed near the end if the list were sorted by the English names
+    assert!(position("Afrique du Sud") < position("Algérie"));
+    assert!(position("Algérie") < position("Angola"));
+}
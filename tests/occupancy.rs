@@ -0,0 +1,85 @@
+// tests for the attribution of days to the countries occupied during them
+
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+
+use takeout_travel_history::core::data::{BorderCrossing, CrossingReason, Region, Source};
+use takeout_travel_history::core::occupancy::{attributed_days, DayAttribution};
+use takeout_travel_history::core::summary::{partition_by_period, Period};
+
+fn crossing(code: &str, timestamp: &str) -> BorderCrossing {
+    BorderCrossing {
+        timestamp: timestamp.parse::<DateTime<Utc>>().unwrap(),
+        new_regions: [Region::from_code(code)].into_iter().collect(),
+        coordinates: None,
+        reason: CrossingReason::RegionChange,
+        source: Source::GPS,
+        flight: None,
+    }
+}
+
+fn codes(regions: &BTreeSet<Region>) -> Vec<&str> {
+    regions.iter().filter_map(|r| r.code()).collect()
+}
+
+/// a flight from France landing in Japan late in the evening of the 2nd
+fn late_arrival() -> (Vec<BorderCrossing>, DateTime<Utc>) {
+    (
+        vec![
+            crossing("FR", "2023-05-01T08:00:00Z"),
+            crossing("JP", "2023-05-02T23:30:00Z"),
+        ],
+        "2023-05-04T12:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+    )
+}
+
+#[test]
+fn any_attribution_counts_the_arrival_day_for_both_countries() {
+    let (crossings, end) = late_arrival();
+    let days = attributed_days(&crossings, end, NaiveTime::MIN, DayAttribution::Any);
+    let arrival = NaiveDate::from_ymd_opt(2023, 5, 2).unwrap();
+    assert_eq!(codes(&days[&arrival]), ["FR", "JP"]);
+}
+
+#[test]
+fn majority_attribution_counts_the_arrival_day_only_where_most_of_it_was_spent() {
+    let (crossings, end) = late_arrival();
+    let days = attributed_days(&crossings, end, NaiveTime::MIN, DayAttribution::Majority);
+    let arrival = NaiveDate::from_ymd_opt(2023, 5, 2).unwrap();
+    assert_eq!(codes(&days[&arrival]), ["FR"]);
+}
+
+#[test]
+fn day_boundary_moves_a_late_arrival_into_the_next_day() {
+    let (crossings, end) = late_arrival();
+    let boundary = NaiveTime::from_hms_opt(23, 0, 0).unwrap();
+    let days = attributed_days(&crossings, end, boundary, DayAttribution::Majority);
+    // the day labeled the 2nd now runs from 23:00 on the 2nd, so the arrival falls in it and most of it is in Japan
+    let arrival = NaiveDate::from_ymd_opt(2023, 5, 2).unwrap();
+    assert_eq!(codes(&days[&arrival]), ["JP"]);
+    let previous = NaiveDate::from_ymd_opt(2023, 5, 1).unwrap();
+    assert_eq!(codes(&days[&previous]), ["FR"]);
+}
+
+#[test]
+fn partition_by_year_groups_crossings_by_their_utc_year() {
+    let crossings = vec![
+        crossing("US", "2022-12-31T23:00:00Z"),
+        crossing("CA", "2023-01-01T01:00:00Z"),
+        crossing("US", "2023-06-01T00:00:00Z"),
+        crossing("MX", "2025-02-01T00:00:00Z"),
+    ];
+    let years = partition_by_period(&crossings, Period::Year, |c| c.timestamp);
+    let starts: Vec<NaiveDate> = years.iter().map(|(start, _)| *start).collect();
+    let sizes: Vec<usize> = years.iter().map(|(_, c)| c.len()).collect();
+    assert_eq!(
+        starts,
+        vec![
+            NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+        ]
+    );
+    assert_eq!(sizes, vec![1, 2, 1]);
+}
@@ -0,0 +1,130 @@
+// tests for the serializations of border crossings and records, and the writer of a command's output
+
+use std::fs;
+use std::io::Read;
+
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use serde_json::Value;
+
+use takeout_travel_history::core::data::{BorderCrossing, CrossingReason, Record, Region, Source};
+use takeout_travel_history::core::display::Listing;
+use takeout_travel_history::core::json::JsonActivityConfidence;
+use takeout_travel_history::core::output::{
+    border_crossings_to_json, border_crossings_to_kepler, records_to_owntracks, write_output,
+};
+use takeout_travel_history::core::semantic::Flight;
+
+fn crossing(code: &str, timestamp: &str, coordinates: (f64, f64)) -> BorderCrossing {
+    BorderCrossing {
+        timestamp: timestamp.parse::<DateTime<Utc>>().unwrap(),
+        new_regions: [Region::from_code(code)].into_iter().collect(),
+        coordinates: Some(coordinates),
+        reason: CrossingReason::RegionChange,
+        source: Source::GPS,
+        flight: None,
+    }
+}
+
+#[test]
+fn compressed_output_gunzips_to_the_uncompressed_output() {
+    let dir = std::env::temp_dir().join(format!("compressed-output-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let contents = "Mon, 1 May 2023 12:00:00 +0000\n    |\n    | Canada";
+    write_output(contents, &Some(dir.join("plain.txt")), false);
+    write_output(contents, &Some(dir.join("compressed.txt")), true);
+
+    let plain = fs::read(dir.join("plain.txt")).unwrap();
+    let mut gunzipped = vec![];
+    GzDecoder::new(fs::File::open(dir.join("compressed.txt.gz")).unwrap())
+        .read_to_end(&mut gunzipped)
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(!plain.is_empty());
+    assert_eq!(gunzipped, plain);
+}
+
+#[test]
+fn kepler_bundle_has_the_dataset_fields_and_a_layer_of_the_dataset() {
+    let crossings = vec![
+        crossing("FR", "2023-09-10T08:00:00Z", (48.8566, 2.3522)),
+        crossing("DE", "2023-09-14T19:00:00Z", (52.52, 13.405)),
+    ];
+    let bundle: Value = serde_json::from_str(&border_crossings_to_kepler(
+        &crossings,
+        &Listing::default(),
+        6,
+    ))
+    .unwrap();
+    let data = &bundle["datasets"][0]["data"];
+    let fields: Vec<&str> = data["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, vec!["lat", "lng", "timestamp", "region"]);
+    assert_eq!(data["allData"].as_array().unwrap().len(), 2);
+    assert_eq!(data["allData"][0][0], 48.8566);
+
+    let layers = bundle["config"]["config"]["visState"]["layers"]
+        .as_array()
+        .unwrap();
+    assert_eq!(layers.len(), 1);
+    assert_eq!(layers[0]["config"]["dataId"], data["id"]);
+}
+
+#[test]
+fn owntracks_locations_have_rounded_coordinates_epoch_seconds_and_accuracy_when_known() {
+    let timestamp = "2023-09-10T08:00:00Z".parse::<DateTime<Utc>>().unwrap();
+    let records = vec![
+        Record {
+            latitude: 48.856_614,
+            longitude: 2.352_222,
+            timestamp,
+            source: Source::GPS,
+            accuracy: Some(12),
+        },
+        Record {
+            latitude: 52.520_008,
+            longitude: 13.404_954,
+            timestamp,
+            source: Source::Wifi,
+            accuracy: None,
+        },
+    ];
+    let locations: Value = serde_json::from_str(&records_to_owntracks(&records, 3)).unwrap();
+    let locations = locations.as_array().unwrap();
+    assert_eq!(locations.len(), 2);
+    assert!(locations.iter().all(|l| l["_type"] == "location"));
+    assert_eq!(locations[0]["lat"], 48.857);
+    assert_eq!(locations[0]["lon"], 2.352);
+    assert_eq!(locations[0]["tst"], 1_694_332_800);
+    assert_eq!(locations[0]["acc"], 12);
+    assert!(locations[1].get("acc").is_none());
+}
+
+#[test]
+fn json_includes_the_flight_of_a_corroborated_crossing() {
+    let flight = Flight {
+        departure: "2023-09-14T15:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+        arrival: "2023-09-14T17:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+        confidence: Some(JsonActivityConfidence::High),
+    };
+    let crossings = vec![
+        crossing("FR", "2023-09-10T08:00:00Z", (48.8566, 2.3522)),
+        BorderCrossing {
+            flight: Some(flight),
+            ..crossing("DE", "2023-09-14T19:00:00Z", (52.52, 13.405))
+        },
+    ];
+    let json: Value = serde_json::from_str(&border_crossings_to_json(
+        &crossings,
+        &Listing::default(),
+        false,
+    ))
+    .unwrap();
+    assert!(json[0].get("flight").is_none());
+    assert_eq!(json[1]["flight"]["arrival"], "2023-09-14T17:00:00+00:00");
+    assert_eq!(json[1]["flight"]["confidence"], "HIGH");
+}
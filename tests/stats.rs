@@ -0,0 +1,42 @@
+// tests for the summary statistics of the stats command
+
+use chrono::{DateTime, Utc};
+
+use takeout_travel_history::core::crossings::records_to_border_crossings;
+use takeout_travel_history::core::data::{Record, Source};
+use takeout_travel_history::core::input::read_records_from_files;
+use takeout_travel_history::core::stats::Stats;
+
+fn record(latitude: f64, longitude: f64, timestamp: &str) -> Record {
+    Record {
+        latitude,
+        longitude,
+        timestamp: timestamp.parse::<DateTime<Utc>>().unwrap(),
+        source: Source::GPS,
+        accuracy: None,
+    }
+}
+
+#[test]
+fn gaps_and_the_first_record_are_not_border_crossings() {
+    // a day apart in Paris, so every record after the first follows a gap, then a move into Germany
+    let mut records: Vec<Record> = (1..=10)
+        .map(|day| record(48.8566, 2.3522, &format!("2023-09-{day:02}T08:00:00Z")))
+        .collect();
+    let stats = Stats::new(&records, &records_to_border_crossings(&records));
+    assert_eq!(stats.crossings, 0);
+
+    records.push(record(52.52, 13.405, "2023-09-10T14:00:00Z"));
+    let stats = Stats::new(&records, &records_to_border_crossings(&records));
+    assert_eq!(stats.crossings, 1);
+    assert_eq!(stats.countries, 2);
+}
+
+#[test]
+fn subdivision_only_records_count_towards_their_country() {
+    let fixture = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/subdivision_only.cache.json");
+    let records = read_records_from_files(&[fixture]);
+    let stats = Stats::new(&records, &records_to_border_crossings(&records));
+    assert_eq!(stats.countries, 1);
+}